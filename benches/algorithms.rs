@@ -0,0 +1,108 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use advent_of_code_2021::{day3, day4, day5, day9, day15, day17, Solution};
+
+fn read_input(day: u8) -> String {
+    let filepath = format!("input_data/day{}_input.txt", day);
+    std::fs::read_to_string(&filepath)
+        .unwrap_or_else(|e| panic!("Error reading file \"{}\"! : {}", filepath, e))
+}
+
+// a report of `word_count` random words of `word_len` bits
+fn generate_report(word_len: usize, word_count: usize) -> day3::DiagnosticReport {
+    let mut rng = StdRng::seed_from_u64(2021);
+    let words = (0..word_count)
+        .map(|_| (0..word_len).map(|_| if rng.gen() { '1' } else { '0' }).collect::<String>())
+        .collect::<Vec<_>>();
+    day3::Day3::parse(&words.join("\n")).unwrap()
+}
+
+fn day3_part2_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day3 part2");
+    group.sample_size(10);
+    for (word_len, word_count) in [(12, 1000), (32, 100_000), (128, 100_000)] {
+        let report = generate_report(word_len, word_count);
+        let size = format!("{}x{}", word_count, word_len);
+        group.bench_with_input(BenchmarkId::new("filtering", &size), &report,
+            |b, report| b.iter(|| day3::life_support_rating(black_box(report))));
+        group.bench_with_input(BenchmarkId::new("trie", &size), &report,
+            |b, report| b.iter(|| day3::life_support_rating_trie(black_box(report))));
+        // the walks alone, once the trie has been built
+        let trie = day3::WordTrie::new(&report);
+        group.bench_with_input(BenchmarkId::new("trie walks", &size), &trie,
+            |b, trie| b.iter(|| {
+                (black_box(trie).find(&day3::BitCriteria::Oxygen), black_box(trie).find(&day3::BitCriteria::Co2))
+            }));
+    }
+    group.finish();
+}
+
+fn day4_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day4");
+    group.sample_size(10);
+    for (board_count, size, max_number) in [(100, 5, 100), (2000, 5, 1000), (500, 10, 1000)] {
+        let game = day4::BingoGame::random(&mut StdRng::seed_from_u64(2021), board_count, size, size, max_number);
+        let name = format!("{} boards of {}x{}, {} numbers", board_count, size, size, max_number);
+        group.bench_with_input(BenchmarkId::new("mark_and_check", &name), &game,
+            |b, game| b.iter(|| black_box(game).play()));
+        group.bench_with_input(BenchmarkId::new("indexed", &name), &game,
+            |b, game| b.iter(|| black_box(game).play_indexed()));
+    }
+    group.finish();
+}
+
+fn day5_part2_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day5 part2");
+    group.sample_size(10);
+    for (count, size) in [(500, 1000), (5000, 1000), (500, 100_000)] {
+        let segments = day5::LineSegments::random(&mut StdRng::seed_from_u64(2021), count, size);
+        let name = format!("{} segments on {}x{}", count, size, size);
+        group.bench_with_input(BenchmarkId::new("rasterize", &name), &segments,
+            |b, segments| b.iter(|| day5::Day5::part2(black_box(segments))));
+        group.bench_with_input(BenchmarkId::new("sweep", &name), &segments,
+            |b, segments| b.iter(|| day5::count_overlaps_sweep(black_box(segments), true)));
+    }
+    group.finish();
+}
+
+fn day9_part2_benchmark(c: &mut Criterion) {
+    let height_map = day9::Day9::parse(&read_input(9)).unwrap();
+
+    let mut group = c.benchmark_group("day9 part2");
+    group.bench_function("stack based",
+        |b| b.iter(|| day9::product_of_largest_basins(black_box(&height_map))));
+    group.bench_function("BasinMap & IDEqualitySet",
+        |b| b.iter(|| day9::product_of_largest_basins_first_try(black_box(&height_map))));
+    group.finish();
+}
+
+fn day15_benchmark(c: &mut Criterion) {
+    let cavern = day15::Day15::parse(&read_input(15)).unwrap();
+    let expanded_cavern = cavern.expand_map();
+
+    for (name, cavern) in [("day15 part1", &cavern), ("day15 part2", &expanded_cavern)] {
+        let (start, end) = cavern.corners();
+        let mut group = c.benchmark_group(name);
+        group.sample_size(10);
+        group.bench_function("find_min_risk",
+            |b| b.iter(|| black_box(cavern).find_min_risk(start, end)));
+        group.bench_function("bidirectional_astar",
+            |b| b.iter(|| black_box(cavern).bidirectional_astar(start, end)));
+        group.finish();
+    }
+}
+
+fn day17_benchmark(c: &mut Criterion) {
+    let target = day17::Day17::parse(&read_input(17)).unwrap();
+
+    let mut group = c.benchmark_group("day17");
+    group.bench_function("find_optimal_pair_and_count_possibilities",
+        |b| b.iter(|| day17::find_optimal_pair_and_count_possibilities(black_box(target))));
+    group.bench_function("find_optimal_pair_and_count_possibilities_interbal",
+        |b| b.iter(|| day17::find_optimal_pair_and_count_possibilities_interbal(black_box(target))));
+    group.finish();
+}
+
+criterion_group!(benches, day3_part2_benchmark, day4_benchmark, day5_part2_benchmark, day9_part2_benchmark, day15_benchmark, day17_benchmark);
+criterion_main!(benches);
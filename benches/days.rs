@@ -0,0 +1,49 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use advent_of_code_2021::*;
+
+fn read_input(day: u8) -> String {
+    let filepath = format!("input_data/day{}_input.txt", day);
+    std::fs::read_to_string(&filepath)
+        .unwrap_or_else(|e| panic!("Error reading file \"{}\"! : {}", filepath, e))
+}
+
+// benchmarks the parse, part 1 and part 2 phases of a day separately, on the real input
+fn bench_day<S: Solution>(c: &mut Criterion, day: u8) {
+    let file_data = read_input(day);
+    let input = S::parse(&file_data)
+        .unwrap_or_else(|e| panic!("Error parsing the input of day {}! : {}", day, e));
+
+    let mut group = c.benchmark_group(format!("day{}", day));
+    group.sample_size(10);
+    group.bench_function("parse", |b| b.iter(|| S::parse(black_box(&file_data))));
+    group.bench_function("part1", |b| b.iter(|| S::part1(black_box(&input))));
+    group.bench_function("part2", |b| b.iter(|| S::part2(black_box(&input))));
+    group.finish();
+}
+
+fn days_benchmark(c: &mut Criterion) {
+    bench_day::<day1::Day1>(c, 1);
+    bench_day::<day2::Day2>(c, 2);
+    bench_day::<day3::Day3>(c, 3);
+    bench_day::<day4::Day4>(c, 4);
+    bench_day::<day5::Day5>(c, 5);
+    bench_day::<day6::Day6>(c, 6);
+    bench_day::<day7::Day7>(c, 7);
+    bench_day::<day8::Day8>(c, 8);
+    bench_day::<day9::Day9>(c, 9);
+    bench_day::<day10::Day10>(c, 10);
+    bench_day::<day11::Day11>(c, 11);
+    bench_day::<day12::Day12>(c, 12);
+    bench_day::<day13::Day13>(c, 13);
+    bench_day::<day14::Day14>(c, 14);
+    bench_day::<day15::Day15>(c, 15);
+    bench_day::<day16::Day16>(c, 16);
+    bench_day::<day17::Day17>(c, 17);
+    bench_day::<day18::Day18>(c, 18);
+    bench_day::<day19::Day19>(c, 19);
+    bench_day::<day20::Day20>(c, 20);
+    bench_day::<day21::Day21>(c, 21);
+}
+
+criterion_group!(benches, days_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use crate::{Answers, Part};
+
+// The answers are stored in a small subset of TOML, with one table per day:
+//
+//     [day1]
+//     part1 = "1532"
+//     part2 = "1571"
+//
+// Values are basic strings, multi-line basic strings (for answers such as day 13's folded paper) or integers.
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseAnswersErrorKind {
+    InvalidHeader, InvalidDay(String), DuplicateDay(u8), KeyOutsideDay, MissingEquals, UnknownKey(String),
+    DuplicateKey(String), InvalidValue, InvalidEscape(char), UnterminatedString, TrailingCharacters,
+}
+impl Display for ParseAnswersErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseAnswersErrorKind::InvalidHeader => write!(f, "invalid table header, expected \"[day<N>]\""),
+            ParseAnswersErrorKind::InvalidDay(s) => write!(f, "invalid day \"{}\"", s),
+            ParseAnswersErrorKind::DuplicateDay(day) => write!(f, "day {} was repeated", day),
+            ParseAnswersErrorKind::KeyOutsideDay => write!(f, "answers must be placed under a \"[day<N>]\" header"),
+            ParseAnswersErrorKind::MissingEquals => write!(f, "expected \"<key> = <value>\""),
+            ParseAnswersErrorKind::UnknownKey(s) => write!(f, "unknown key \"{}\", expected \"part1\" or \"part2\"", s),
+            ParseAnswersErrorKind::DuplicateKey(s) => write!(f, "key \"{}\" was repeated", s),
+            ParseAnswersErrorKind::InvalidValue => write!(f, "value must be a string or an integer"),
+            ParseAnswersErrorKind::InvalidEscape(c) => write!(f, "invalid escape sequence \"\\{}\"", c),
+            ParseAnswersErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            ParseAnswersErrorKind::TrailingCharacters => write!(f, "unexpected characters after the value"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseAnswersError {
+    pub line: usize,
+    pub kind: ParseAnswersErrorKind,
+}
+impl Display for ParseAnswersError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}", self.kind, self.line)
+    }
+}
+impl Error for ParseAnswersError {}
+
+/// The result of comparing a solution's answer against the stored one.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Verdict {
+    Pass, Changed, Unknown
+}
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Pass => write!(f, "pass"),
+            Verdict::Changed => write!(f, "changed"),
+            Verdict::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// The known-correct answers of each day, as read from and written to an answers file.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct AnswerStore {
+    days: BTreeMap<u8, Answers>,
+}
+impl AnswerStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, day: u8, part: Part) -> Option<&str> {
+        let answers = self.days.get(&day)?;
+        match part {
+            Part::One => answers.part1.as_deref(),
+            Part::Two => answers.part2.as_deref(),
+        }
+    }
+
+    pub fn set(&mut self, day: u8, part: Part, ans: String) {
+        let answers = self.days.entry(day).or_default();
+        match part {
+            Part::One => answers.part1 = Some(ans),
+            Part::Two => answers.part2 = Some(ans),
+        }
+    }
+
+    pub fn verdict(&self, day: u8, part: Part, ans: &str) -> Verdict {
+        match self.get(day, part) {
+            Some(expected) if expected == ans => Verdict::Pass,
+            Some(_) => Verdict::Changed,
+            None => Verdict::Unknown,
+        }
+    }
+}
+
+impl FromStr for AnswerStore {
+    type Err = ParseAnswersError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut store = AnswerStore::new();
+        let mut current_day = None;
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i+1, line));
+
+        while let Some((line_number, line)) = lines.next() {
+            let err = |kind| ParseAnswersError { line: line_number, kind };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = strip_comment(header).strip_suffix(']').ok_or_else(|| err(ParseAnswersErrorKind::InvalidHeader))?;
+                let day = header.trim().strip_prefix("day").ok_or_else(|| err(ParseAnswersErrorKind::InvalidHeader))?;
+                let day = match day.parse::<u8>() {
+                    Ok(x) if x > 0 => x,
+                    _ => return Err(err(ParseAnswersErrorKind::InvalidDay(day.to_owned()))),
+                };
+                if store.days.insert(day, Answers::default()).is_some() {
+                    return Err(err(ParseAnswersErrorKind::DuplicateDay(day)));
+                }
+                current_day = Some(day);
+                continue;
+            }
+
+            let day = current_day.ok_or_else(|| err(ParseAnswersErrorKind::KeyOutsideDay))?;
+            let (key, value) = line.split_once('=').ok_or_else(|| err(ParseAnswersErrorKind::MissingEquals))?;
+            let part = match key.trim() {
+                "part1" => Part::One,
+                "part2" => Part::Two,
+                key => return Err(err(ParseAnswersErrorKind::UnknownKey(key.to_owned()))),
+            };
+            if store.get(day, part).is_some() {
+                return Err(err(ParseAnswersErrorKind::DuplicateKey(key.trim().to_owned())));
+            }
+
+            let value = value.trim();
+            let ans = if let Some(rest) = value.strip_prefix("\"\"\"") {
+                // a newline immediately after the opening delimiter is not part of the string
+                let mut raw = String::from(rest);
+                let end = loop {
+                    if let Some(end) = find_closing(&raw, "\"\"\"") { break end; }
+                    let (_, next_line) = lines.next().ok_or_else(|| err(ParseAnswersErrorKind::UnterminatedString))?;
+                    raw.push('\n');
+                    raw.push_str(next_line);
+                };
+                if !strip_comment(&raw[end+3..]).is_empty() {
+                    return Err(err(ParseAnswersErrorKind::TrailingCharacters));
+                }
+                let raw = raw[..end].strip_prefix('\n').unwrap_or(&raw[..end]);
+                unescape(raw).map_err(err)?
+            }
+            else if let Some(rest) = value.strip_prefix('"') {
+                let end = find_closing(rest, "\"").ok_or_else(|| err(ParseAnswersErrorKind::UnterminatedString))?;
+                if !strip_comment(&rest[end+1..]).is_empty() {
+                    return Err(err(ParseAnswersErrorKind::TrailingCharacters));
+                }
+                unescape(&rest[..end]).map_err(err)?
+            }
+            else {
+                let value = strip_comment(value);
+                value.parse::<i64>().map_err(|_| err(ParseAnswersErrorKind::InvalidValue))?;
+                value.to_owned()
+            };
+            store.set(day, part, ans);
+        }
+
+        Ok(store)
+    }
+}
+
+impl Display for AnswerStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, (day, answers)) in self.days.iter().enumerate() {
+            if i != 0 { writeln!(f)?; }
+            writeln!(f, "[day{}]", day)?;
+            for (key, ans) in [("part1", &answers.part1), ("part2", &answers.part2)] {
+                let ans = match ans {
+                    Some(x) => x,
+                    None => continue,
+                };
+                if ans.contains('\n') {
+                    writeln!(f, "{} = \"\"\"\n{}\"\"\"", key, escape(ans))?;
+                }
+                else {
+                    writeln!(f, "{} = \"{}\"", key, escape(ans))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// removes a trailing comment and any whitespace around what remains
+fn strip_comment(s: &str) -> &str {
+    match s.find('#') {
+        Some(i) => s[..i].trim(),
+        None => s.trim(),
+    }
+}
+
+// the index of the first unescaped `delimiter` in `s`
+fn find_closing(s: &str, delimiter: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            _ if s[i..].starts_with(delimiter) => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\t' => escaped.push_str("\\t"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(s: &str) -> Result<String, ParseAnswersErrorKind> {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('"') => unescaped.push('"'),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some(c) => return Err(ParseAnswersErrorKind::InvalidEscape(c)),
+            None => return Err(ParseAnswersErrorKind::UnterminatedString),
+        }
+    }
+    Ok(unescaped)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let answers =
+            "# known answers
+            [day1]
+            part1 = 1532
+            part2 = \"1571\"  # a comment
+
+            [day13]
+            part2 = \"\"\"
+            #..#
+            .##.\"\"\"";
+
+        let store = answers.parse::<AnswerStore>().unwrap();
+        assert_eq!(store.get(1, Part::One), Some("1532"));
+        assert_eq!(store.get(1, Part::Two), Some("1571"));
+        assert_eq!(store.get(13, Part::One), None);
+        assert_eq!(store.get(13, Part::Two), Some("            #..#\n            .##."));
+        assert_eq!(store.verdict(1, Part::One, "1532"), Verdict::Pass);
+        assert_eq!(store.verdict(1, Part::Two, "1572"), Verdict::Changed);
+        assert_eq!(store.verdict(2, Part::One, "0"), Verdict::Unknown);
+        assert_eq!(store.to_string().parse::<AnswerStore>(), Ok(store));
+
+        let err = "[day1]\npart3 = 1".parse::<AnswerStore>().unwrap_err();
+        assert_eq!(err, ParseAnswersError { line: 2, kind: ParseAnswersErrorKind::UnknownKey("part3".to_owned()) });
+    }
+}
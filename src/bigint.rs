@@ -0,0 +1,160 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Add, Mul};
+
+// the largest power of 10 which fits in a limb, so each limb-sized chunk of decimal digits has a fixed width
+const DECIMAL_CHUNK: u64 = 10_000_000_000_000_000_000;
+const DECIMAL_CHUNK_DIGITS: usize = 19;
+
+/// An unsigned integer of any size, for answers which don't fit in a primitive integer.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct BigUint {
+    limbs: Vec<u64>,    // least significant first, without trailing zeros
+}
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint::default()
+    }
+
+    /// Creates an integer from its 64-bit limbs, least significant first.
+    pub fn from_limbs(limbs: Vec<u64>) -> Self {
+        let mut n = BigUint { limbs };
+        n.normalize();
+        n
+    }
+
+    pub fn limbs(&self) -> &[u64] {
+        &self.limbs
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// The number of bits needed to write the integer, which is 0 for zero.
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(last) => self.limbs.len()*64 - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn normalize(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+impl From<u64> for BigUint {
+    fn from(x: u64) -> Self {
+        BigUint::from_limbs(vec![x])
+    }
+}
+impl From<u128> for BigUint {
+    fn from(x: u128) -> Self {
+        BigUint::from_limbs(vec![x as u64, (x >> 64) as u64])
+    }
+}
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Add for &BigUint {
+    type Output = BigUint;
+    fn add(self, rhs: Self) -> BigUint {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(rhs.limbs.len()) + 1);
+        let mut carry = false;
+        for i in 0..self.limbs.len().max(rhs.limbs.len()) {
+            let a = self.limbs.get(i).copied().unwrap_or_default();
+            let b = rhs.limbs.get(i).copied().unwrap_or_default();
+            let (sum, overflow1) = a.overflowing_add(b);
+            let (sum, overflow2) = sum.overflowing_add(carry as u64);
+            limbs.push(sum);
+            carry = overflow1 || overflow2;
+        }
+        limbs.push(carry as u64);
+        BigUint::from_limbs(limbs)
+    }
+}
+impl Add for BigUint {
+    type Output = BigUint;
+    fn add(self, rhs: Self) -> BigUint {
+        &self + &rhs
+    }
+}
+impl Mul for &BigUint {
+    type Output = BigUint;
+    fn mul(self, rhs: Self) -> BigUint {
+        let mut limbs = vec![0; self.limbs.len() + rhs.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in rhs.limbs.iter().enumerate() {
+                let product = a as u128 * b as u128 + limbs[i+j] as u128 + carry;
+                limbs[i+j] = product as u64;
+                carry = product >> 64;
+            }
+            limbs[i + rhs.limbs.len()] = carry as u64;
+        }
+        BigUint::from_limbs(limbs)
+    }
+}
+impl Mul for BigUint {
+    type Output = BigUint;
+    fn mul(self, rhs: Self) -> BigUint {
+        &self * &rhs
+    }
+}
+impl Display for BigUint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // split into chunks of decimal digits by repeatedly dividing by `DECIMAL_CHUNK`
+        let mut chunks = Vec::new();
+        let mut limbs = self.limbs.clone();
+        while !limbs.is_empty() {
+            let mut remainder = 0u128;
+            for limb in limbs.iter_mut().rev() {
+                let current = (remainder << 64) | *limb as u128;
+                *limb = (current / DECIMAL_CHUNK as u128) as u64;
+                remainder = current % DECIMAL_CHUNK as u128;
+            }
+            chunks.push(remainder as u64);
+            while limbs.last() == Some(&0) {
+                limbs.pop();
+            }
+        }
+
+        let mut digits = match chunks.pop() {
+            Some(most_significant) => most_significant.to_string(),
+            None => "0".to_owned(),
+        };
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:0width$}", chunk, width = DECIMAL_CHUNK_DIGITS));
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let max = BigUint::from(u128::MAX);
+        assert_eq!(max.to_string(), u128::MAX.to_string());
+        assert_eq!((&max + &BigUint::from(1u64)).limbs(), &[0, 0, 1]);
+        assert_eq!((&max * &max).to_string(),
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025");
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(format!("{:>5}", BigUint::from(42u64)), "   42");
+        assert_eq!(BigUint::from(1u64 << 63).bits(), 64);
+        assert!(BigUint::from(u64::MAX) < max);
+    }
+}
@@ -1,79 +1,260 @@
-use std::vec;
-
-struct SlidingWindow {
-    prev1: u16,
-    prev2: u16,
-    remaining: vec::IntoIter<u16>
-}
-impl SlidingWindow {
-    fn new(measurements: Vec<u16>) -> SlidingWindow {
-        let mut remaining =  measurements.into_iter();
-        return SlidingWindow {
-            prev1: remaining.next().unwrap_or_default(),
-            prev2: remaining.next().unwrap_or_default(),
-            remaining: remaining
-        }
+use std::iter::Sum;
+use std::cmp::Ordering;
+use std::io::BufRead;
+use std::collections::VecDeque;
+use crate::{read_lines, Part, PartAnswers, Solution, PuzzleError};
+
+/// An iterator adaptor which reduces each window of `size` consecutive elements of `iter` with `reduce`, such as
+/// `window_sum` or `window_max`. Like `slice::windows`, the windows overlap and there are none if `iter` is too short.
+pub struct SlidingWindow<I: Iterator, F> {
+    iter: I,
+    window: VecDeque<I::Item>,
+    size: usize,
+    reduce: F,
+}
+impl<I: Iterator, F> SlidingWindow<I, F> {
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn new(iter: I, size: usize, reduce: F) -> Self {
+        assert!(size > 0, "the window size must be positive");
+        SlidingWindow { iter, window: VecDeque::with_capacity(size), size, reduce }
     }
 }
-impl Iterator for SlidingWindow {
-    type Item = u16;
+impl<I, F, U> Iterator for SlidingWindow<I, F>
+where
+    I: Iterator,
+    F: FnMut(&[I::Item]) -> U,
+{
+    type Item = U;
     fn next(&mut self) -> Option<Self::Item> {
-        self.remaining.next().map(|x| {
-            let window_sum = self.prev1 + self.prev2 + x;
-            self.prev1 = self.prev2;
-            self.prev2 = x;
-            return window_sum;
-        })
-
+        if self.window.len() == self.size { self.window.pop_front(); }
+        while self.window.len() < self.size {
+            self.window.push_back(self.iter.next()?);
+        }
+        Some((self.reduce)(self.window.make_contiguous()))
     }
 }
 
-pub fn day1_main(file_data: &str) {
-    // parse to vector of u16 values
-    let measurements: Vec<u16> = file_data.trim().lines()
-        .enumerate().map(|(i, line)| {
-            line.parse::<u16>().unwrap_or_else(|e| {
-                eprintln!("ParseIntError on line {}: {}", i+1, e);
-                std::process::exit(1);
-            })
-        }).collect();
+pub fn window_sum<T: Copy + Sum>(window: &[T]) -> T {
+    window.iter().copied().sum()
+}
 
+pub fn window_mean<T: Copy + Into<f64>>(window: &[T]) -> f64 {
+    window.iter().map(|&x| x.into()).sum::<f64>() / window.len() as f64
+}
 
-    // Part 1
-    let mut measurement_iter = measurements.iter();
+pub fn window_min<T: Copy + Ord>(window: &[T]) -> T {
+    window.iter().copied().min().expect("windows are never empty")
+}
+
+pub fn window_max<T: Copy + Ord>(window: &[T]) -> T {
+    window.iter().copied().max().expect("windows are never empty")
+}
+
+fn count_increments<T: PartialOrd, I: Iterator<Item = T>>(mut measurements: I) -> usize {
     let mut increment_count = 0;
-    let mut prev  = match measurement_iter.next() {
+    let mut prev = match measurements.next() {
         Some(val) => val,
-        None => {
-            println!("File is empty!");
-            return;
-        }
+        None => return 0,
     };
-
-    for current in measurement_iter {
+    for current in measurements {
         if current > prev {
             increment_count += 1;
         }
         prev = current;
     }
-    println!("{} measurements are larger than the previous.", increment_count);
+    increment_count
+}
+
+/// The number of windows of `size` consecutive measurements whose sum is larger than the previous window's sum.
+///
+/// # Panics
+///
+/// Panics if `size` is 0.
+pub fn count_window_increases(measurements: &[u16], size: usize) -> usize {
+    let measurements = measurements.iter().map(|&x| u64::from(x));
+    count_increments(SlidingWindow::new(measurements, size, window_sum))
+}
 
+/// The direction of the change from one measurement to the next.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Trend {
+    Increasing, Decreasing, Flat
+}
 
-    // Part 2
-    let mut window_iter = SlidingWindow::new(measurements);
-    increment_count = 0;
-    let mut prev_sum = match window_iter.next() {
-        Some(val) => val,
-        None => {
-            println!("File is empty!");
-            return;
+/// A maximal run of consecutive changes in the same direction, from the measurement at index `start` to the one at
+/// index `end`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TrendRun {
+    pub trend: Trend,
+    pub start: usize,
+    pub end: usize,
+}
+impl TrendRun {
+    /// The number of changes in the run.
+    pub fn steps(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+/// The measurements split into runs of increases, decreases and flat stretches, such as a depth profile smoothed by a
+/// `SlidingWindow`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TrendProfile {
+    runs: Vec<TrendRun>,
+}
+impl TrendProfile {
+    /// Segments the measurements in a single pass, without storing them.
+    pub fn new<T: PartialOrd, I: IntoIterator<Item = T>>(measurements: I) -> Self {
+        let mut runs: Vec<TrendRun> = Vec::new();
+        let mut measurements = measurements.into_iter();
+        let mut prev = match measurements.next() {
+            Some(x) => x,
+            None => return TrendProfile { runs },
+        };
+        for (i, current) in measurements.enumerate().map(|(i, x)| (i+1, x)) {
+            // incomparable measurements (such as NaN means) are treated as flat
+            let trend = match current.partial_cmp(&prev) {
+                Some(Ordering::Greater) => Trend::Increasing,
+                Some(Ordering::Less) => Trend::Decreasing,
+                _ => Trend::Flat,
+            };
+            match runs.last_mut() {
+                Some(run) if run.trend == trend => run.end = i,
+                _ => runs.push(TrendRun { trend, start: i-1, end: i }),
+            }
+            prev = current;
         }
-    };
-    for current_sum in window_iter {
-        if current_sum > prev_sum {
-            increment_count += 1;
+        TrendProfile { runs }
+    }
+
+    pub fn runs(&self) -> &[TrendRun] {
+        &self.runs
+    }
+
+    /// The longest run of decreases, or the first one if several are the longest.
+    pub fn longest_descent(&self) -> Option<TrendRun> {
+        self.runs.iter().copied()
+            .filter(|run| run.trend == Trend::Decreasing)
+            .fold(None, |longest: Option<TrendRun>, run| match longest {
+                Some(longest) if longest.steps() >= run.steps() => Some(longest),
+                _ => Some(run),
+            })
+    }
+
+    /// The indices of the measurements where the trend changes direction.
+    pub fn trend_changes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.runs.iter().skip(1).map(|run| run.start)
+    }
+
+    /// The total number of changes in the direction of `trend`.
+    pub fn count(&self, trend: Trend) -> usize {
+        self.runs.iter().filter(|run| run.trend == trend).map(TrendRun::steps).sum()
+    }
+}
+
+pub struct Day1;
+impl Solution for Day1 {
+    type Input = Vec<u16>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        // shares the line handling of the reader, so trailing blank lines are skipped either way
+        Self::parse_reader(file_data.as_bytes())
+    }
+
+    fn parse_reader<R: BufRead>(reader: R) -> Result<Self::Input, PuzzleError> {
+        read_lines(reader).map(|line| {
+            let (line_number, line) = line?;
+            parse_measurement(line_number, &line)
+        }).collect()
+    }
+
+    fn solve_stream<R: BufRead>(reader: R, part: Option<Part>) -> Result<PartAnswers<Self>, PuzzleError> {
+        // a window's sum is larger than the previous one's exactly when the measurement entering the window is
+        // larger than the one leaving it, so only the last three measurements need to be kept
+        let mut last_three = [0; 3];
+        let (mut part1_count, mut part2_count) = (0, 0);
+        for (i, line) in read_lines(reader).enumerate() {
+            let (line_number, line) = line?;
+            let current = parse_measurement(line_number, &line)?;
+            if i >= 1 && current > last_three[(i-1) % 3] { part1_count += 1; }
+            if i >= 3 && current > last_three[i % 3] { part2_count += 1; }
+            last_three[i % 3] = current;
         }
-        prev_sum = current_sum;
+        Ok((
+            (part != Some(Part::Two)).then_some(part1_count),
+            (part != Some(Part::One)).then_some(part2_count),
+        ))
+    }
+
+    fn part1(measurements: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(count_window_increases(measurements, 1))
+    }
+
+    fn part2(measurements: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        Ok(count_window_increases(measurements, 3))
+    }
+}
+
+fn parse_measurement(line_number: usize, line: &str) -> Result<u16, PuzzleError> {
+    line.trim().parse::<u16>().map_err(|e| PuzzleError::at_line(line_number, e))
+}
+
+pub fn day1_main(file_data: &str) -> Result<(usize, usize), PuzzleError> {
+    let measurements = Day1::parse(file_data)?;
+
+    // Part 1
+    let part1_count = Day1::part1(&measurements)?;
+    println!("{} measurements are larger than the previous.", part1_count);
+
+    // Part 2
+    let part2_count = Day1::part2(&measurements)?;
+    println!("{} measurements windows are larger than the previous.", part2_count);
+
+    // Depth profile
+    let profile = TrendProfile::new(measurements.iter());
+    println!("The trend changes direction {} times.", profile.trend_changes().count());
+    if let Some(descent) = profile.longest_descent() {
+        println!("The longest descent is {} measurements long, from measurement {} to {}.",
+            descent.steps() + 1, descent.start + 1, descent.end + 1);
+    }
+
+    Ok((part1_count, part2_count))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let test_data = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+        assert_eq!(day1_main(test_data).unwrap(), (7, 5));
+
+        let measurements = Day1::parse(test_data).unwrap();
+        assert_eq!(count_window_increases(&measurements, 10), 0);
+        assert_eq!(SlidingWindow::new(measurements.iter().copied(), 4, window_max).collect::<Vec<_>>(),
+            vec![210, 210, 210, 240, 269, 269, 269]);
+        assert_eq!(SlidingWindow::new(measurements.iter().copied(), 2, window_min).last(), Some(260));
+        assert_eq!(SlidingWindow::new([1u8, 2, 4].into_iter(), 2, window_mean).collect::<Vec<_>>(), vec![1.5, 3.0]);
+        assert_eq!(SlidingWindow::new(measurements.iter().copied(), 11, window_sum).next(), None);
+
+        let profile = TrendProfile::new(measurements.iter());
+        assert_eq!(profile.count(Trend::Increasing), 7);
+        assert_eq!(profile.trend_changes().collect::<Vec<_>>(), vec![3, 4, 7, 8]);
+        assert_eq!(profile.longest_descent(), Some(TrendRun { trend: Trend::Decreasing, start: 3, end: 4 }));
+        let smoothed = TrendProfile::new(SlidingWindow::new(measurements.into_iter(), 3, window_sum));
+        assert_eq!(smoothed.count(Trend::Increasing), 5);
+        assert_eq!(smoothed.runs()[1], TrendRun { trend: Trend::Flat, start: 1, end: 2 });
+
+        // parsing a string and parsing a reader both skip trailing blank lines
+        let padded = format!("{}\n\n\n", test_data);
+        assert_eq!(Day1::parse(&padded).unwrap(), Day1::parse_reader(padded.as_bytes()).unwrap());
+        assert_eq!(Day1::parse(&padded).unwrap(), Day1::parse(test_data).unwrap());
+        assert!(Day1::parse("199\n\n200").is_err());
     }
-    println!("{} measurements windows are larger than the previous.", increment_count);
 }
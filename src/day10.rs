@@ -1,17 +1,29 @@
 use std::cmp::Ordering;
 use std::fmt;
 use std::ops;
+use std::error::Error;
+use std::io::BufRead;
+use crate::{read_lines, Part, PartAnswers, Solution, PuzzleError};
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(Debug)]
+pub struct ParseBracketError(char);
+impl fmt::Display for ParseBracketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid bracket '{}'", self.0)
+    }
+}
+impl Error for ParseBracketError {}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum BracketType {
     Round, Square, Curly, Angle
 }
-#[derive(PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum BracketMode {
     Open, Close
 }
-#[derive(Clone, Copy)]
-struct Bracket(BracketType, BracketMode);
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Bracket(BracketType, BracketMode);
 impl Bracket {
     fn parse_bracket(c: char) -> Option<Self> {
         match c {
@@ -70,6 +82,10 @@ impl Score {
     fn new(val: u64, m_type: ScoreType) -> Self {
         Score { val, m_type }
     }
+
+    pub fn val(&self) -> u64 {
+        self.val
+    }
 }
 impl Ord for Score {
     fn cmp(&self, other: &Self) -> Ordering {
@@ -78,7 +94,7 @@ impl Ord for Score {
 }
 impl PartialOrd for Score {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.val.partial_cmp(&other.val)
+        Some(self.cmp(other))
     }
 }
 impl PartialEq for Score {
@@ -136,28 +152,35 @@ impl BracketStack {
     }
 }
 
-fn get_score(line: &str) -> Result<Score, usize> {
+// on failure, returns the column of the invalid character along with the error
+fn parse_line(line: &str) -> Result<Vec<Bracket>, (usize, ParseBracketError)> {
+    let indent = line.len() - line.trim_start().len();
+    line.trim().chars().enumerate()
+        .map(|(j, c)| Bracket::parse_bracket(c).ok_or((indent+j+1, ParseBracketError(c))))
+        .collect()
+}
+
+fn get_score(line: &[Bracket]) -> Score {
     let mut stack = BracketStack::new();
-    for (j, c) in line.trim().chars().enumerate() {
-        let bracket = Bracket::parse_bracket(c).ok_or(j+1)?;
+    for &bracket in line {
         if !stack.add_bracket(bracket) {
-            return Ok(Score::new(bracket.get_score_val(ScoreType::Error), ScoreType::Error));
+            return Score::new(bracket.get_score_val(ScoreType::Error), ScoreType::Error);
         }
     }
-    Ok(stack.0.iter().rev().fold(Score{ val: 0, m_type: ScoreType::Completion }, |acc, bracket| {
+    stack.0.iter().rev().fold(Score{ val: 0, m_type: ScoreType::Completion }, |acc, bracket| {
         (acc * 5) + bracket.get_score_val(ScoreType::Completion)
-    }))
+    })
 }
 
+fn get_total_scores(lines: &[Vec<Bracket>]) -> (Score, Option<Score>) {
+    sum_scores(lines.iter().map(|line| get_score(line)))
+}
 
-pub fn day10_main(file_data: &str) -> (Score, Score) {
+// the completion score is `None` if there are no incomplete lines
+fn sum_scores<I: Iterator<Item = Score>>(scores: I) -> (Score, Option<Score>) {
     let mut part1_score = Score::new(0, ScoreType::Error);
-    let mut completion_scores = file_data
-        .lines().enumerate()
-        .flat_map(|(i, line)| {
-            let score = get_score(line).unwrap_or_else(|j|
-                panic!("Error parsing: invalid character at line {} ({})", i, j)
-            );
+    let mut completion_scores = scores
+        .flat_map(|score| {
             if matches!(score.m_type, ScoreType::Completion) { return Some(score); }
             part1_score += score;
             None
@@ -165,12 +188,71 @@ pub fn day10_main(file_data: &str) -> (Score, Score) {
         .collect::<Vec<_>>();
 
     completion_scores.sort_unstable();
-    let part2_score = completion_scores[completion_scores.len() / 2];
+    let part2_score = completion_scores.get(completion_scores.len() / 2).copied();
+    (part1_score, part2_score)
+}
+
+pub struct Day10;
+impl Solution for Day10 {
+    type Input = Vec<Vec<Bracket>>;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        // shares the line handling of the reader, so trailing blank lines are skipped either way
+        Self::parse_reader(file_data.as_bytes())
+    }
+
+    fn parse_reader<R: BufRead>(reader: R) -> Result<Self::Input, PuzzleError> {
+        read_lines(reader)
+            .map(|line| {
+                let (line_number, line) = line?;
+                parse_line(&line).map_err(|(j, e)| PuzzleError::at(line_number, j, e))
+            })
+            .collect()
+    }
+
+    fn solve_stream<R: BufRead>(reader: R, part: Option<Part>) -> Result<PartAnswers<Self>, PuzzleError> {
+        // each line is scored as soon as it is read, so only the scores are kept
+        let scores = read_lines(reader)
+            .map(|line| {
+                let (line_number, line) = line?;
+                let brackets = parse_line(&line).map_err(|(j, e)| PuzzleError::at(line_number, j, e))?;
+                Ok(get_score(&brackets))
+            })
+            .collect::<Result<Vec<_>, PuzzleError>>()?;
+        let (part1_score, part2_score) = sum_scores(scores.into_iter());
+
+        let part1 = (part != Some(Part::Two)).then_some(part1_score.val());
+        let part2 = match part {
+            Some(Part::One) => None,
+            _ => Some(part2_score.ok_or_else(no_incomplete_lines)?.val()),
+        };
+        Ok((part1, part2))
+    }
+
+    fn part1(lines: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(get_total_scores(lines).0.val())
+    }
+
+    fn part2(lines: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        get_total_scores(lines).1.map(|score| score.val()).ok_or_else(no_incomplete_lines)
+    }
+}
+
+fn no_incomplete_lines() -> PuzzleError {
+    PuzzleError::no_solution("there are no incomplete lines")
+}
+
+pub fn day10_main(file_data: &str) -> Result<(Score, Score), PuzzleError> {
+    let lines = Day10::parse(file_data)?;
+    let (part1_score, part2_score) = get_total_scores(&lines);
+    let part2_score = part2_score.ok_or_else(no_incomplete_lines)?;
 
     println!("[Part 1] The total syntax error score is {}.", part1_score);
     println!("[Part 2] The autocomplete score is {}.", part2_score);
 
-    (part1_score, part2_score)
+    Ok((part1_score, part2_score))
 }
 
 #[cfg(test)]
@@ -191,7 +273,12 @@ mod tests {
             <{([([[(<>()){}]>(<<{{
             <{([{{}}[<[[[<>{}]]]>[]]";
 
-        assert_eq!(day10_main(test_data),
+        assert_eq!(day10_main(test_data).unwrap(),
             (Score::new(26397, ScoreType::Error), Score::new(288957, ScoreType::Completion)));
+        assert_eq!(Day10::solve_stream(format!("{}\n\n", test_data).as_bytes(), None).unwrap(),
+            (Some(26397), Some(288957)));
+        let padded = format!("{}\n\n", test_data);
+        assert_eq!(Day10::parse(&padded).unwrap(), Day10::parse_reader(padded.as_bytes()).unwrap());
+        assert_eq!(Day10::part2(&Day10::parse(&padded).unwrap()).unwrap(), 288957);
     }
 }
\ No newline at end of file
@@ -1,65 +1,45 @@
 use std::iter;
 use std::str;
-use std::num::Wrapping;
+use std::fmt;
+use std::error::Error;
 use core::ops::{Index, IndexMut};
 use std::collections::HashSet;
+use crate::grid::{Grid, Point};
+use crate::{Solution, PuzzleError};
 
-const GRID_SIZE: usize = 10;
-type Point = (usize, usize);
-
-// allows overflow and points outside the grid
-fn get_neighbours(p: Point) -> [Point; 8] {
-    let mut neighbours = [(0, 0); 8];
-    let u = (Wrapping(p.0)-Wrapping(1)).0;
-    let d = (Wrapping(p.0)+Wrapping(1)).0;
-    let l = (Wrapping(p.1)-Wrapping(1)).0;
-    let r = (Wrapping(p.1)+Wrapping(1)).0;
-    neighbours[0] = (u, l);
-    neighbours[1] = (u, p.1);
-    neighbours[2] = (u, r);
-    neighbours[3] = (p.0, r);
-    neighbours[4] = (d, r);
-    neighbours[5] = (d, p.1);
-    neighbours[6] = (d, l);
-    neighbours[7] = (p.0, l);
-    neighbours
+
+#[derive(Debug)]
+pub enum ParseOctopusGridError {
+    InvalidEnergy(char)
 }
+impl fmt::Display for ParseOctopusGridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseOctopusGridError::InvalidEnergy(c) => write!(f, "invalid energy level '{}', expected a digit", c),
+        }
+    }
+}
+impl Error for ParseOctopusGridError {}
 
 #[derive(Clone, PartialEq, Debug)]
-struct OctopusGrid(Box<[[u8; GRID_SIZE]; GRID_SIZE]>);
+pub struct OctopusGrid(Grid<u8>);
 impl OctopusGrid {
     fn trigger_point(&mut self, point: Point) -> bool {
-        if point.0 > GRID_SIZE-1 || point.1 > GRID_SIZE-1 { return false; }
         self[point] += 1;
         self[point] > 9
     }
 }
 impl str::FromStr for OctopusGrid {
-    type Err = Point;
+    type Err = PuzzleError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.lines().enumerate().flat_map(|(i, line)| {
-            line.trim().chars().enumerate().map(move |(j, c)| {
-                c.to_digit(10).map(|x| x as u8).ok_or((i, j))
-            })
-        })
-        .collect()
-    }
-}
-impl iter::FromIterator<u8> for OctopusGrid {
-    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
-        let mut octopus_grid = OctopusGrid(Box::new([[0; GRID_SIZE]; GRID_SIZE]));
-        let mut p: Point = (0, 0);
-        for energy in iter {
-            octopus_grid[p] = energy;
-            if p == (GRID_SIZE-1, GRID_SIZE-1) { break; }
-            else if p.1 == GRID_SIZE-1 { p.0 += 1; p.1 = 0; }
-            else { p.1 += 1; }
-        }
-        octopus_grid
+        let grid = Grid::parse_with(s, |c| {
+            c.to_digit(10).map(|x| x as u8).ok_or(ParseOctopusGridError::InvalidEnergy(c))
+        })?;
+        Ok(OctopusGrid(grid))
     }
 }
 impl iter::IntoIterator for OctopusGrid {
-    type Item = u8;
+    type Item = usize;
     type IntoIter = OctopusGridIntoIter;
     fn into_iter(self) -> Self::IntoIter {
         OctopusGridIntoIter {
@@ -72,37 +52,37 @@ impl iter::IntoIterator for OctopusGrid {
 impl Index<Point> for OctopusGrid {
     type Output = u8;
     fn index(&self, index: Point) -> &Self::Output {
-        &self.0[index.0][index.1]
+        &self.0[index]
     }
 }
 impl IndexMut<Point> for OctopusGrid {
     fn index_mut(&mut self, index: Point) -> &mut Self::Output {
-        &mut self.0[index.0][index.1]
+        &mut self.0[index]
     }
 }
 
 #[derive(Clone)]
-struct OctopusGridIntoIter {
+pub struct OctopusGridIntoIter {
     grid: OctopusGrid,
     stack: Vec<Point>,
     flashers: HashSet<Point>
 }
+// yields the number of flashes in each step
 impl Iterator for OctopusGridIntoIter {
-    type Item = u8;
+    type Item = usize;
     fn next(&mut self) -> Option<Self::Item> {
         // increment energy levels
-        for i in 0..GRID_SIZE {
-            for j in 0..GRID_SIZE {
-                if self.grid.trigger_point((i, j)) {
-                    self.stack.push((i, j));
-                    self.flashers.insert((i, j));
-                }
+        for p in self.grid.0.positions() {
+            if self.grid.trigger_point(p) {
+                self.stack.push(p);
+                self.flashers.insert(p);
             }
         }
 
         // trigger neighbours of flashers
         while let Some(point) = self.stack.pop() {
-            for &p in get_neighbours(point).iter() {
+            let neighbours = self.grid.0.neighbours8(point).collect::<Vec<_>>();
+            for p in neighbours {
                 if !self.flashers.contains(&p) && self.grid.trigger_point(p) {
                     self.stack.push(p);
                     self.flashers.insert(p);
@@ -115,7 +95,7 @@ impl Iterator for OctopusGridIntoIter {
             self.grid[p] = 0;
         }
 
-        let ret_val = self.flashers.len() as u8;
+        let ret_val = self.flashers.len();
         self.flashers.clear();
         Some(ret_val)
     }
@@ -124,19 +104,36 @@ impl Iterator for OctopusGridIntoIter {
 
 
 
-pub fn day11_main(file_data: &str) -> (u16, usize) {
-    let mut octopus_grid_iter = file_data.parse::<OctopusGrid>()
-        .unwrap_or_else(|(i, j)| {
-            panic!("Error parsing energy level of octopus in position ({}, {}).", i, j);
-        })
-        .into_iter();
+pub struct Day11;
+impl Solution for Day11 {
+    type Input = OctopusGrid;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-    let flash_count: u16 = octopus_grid_iter.clone().take(100).map(|x| x as u16).sum();
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        file_data.parse::<OctopusGrid>()
+    }
+
+    fn part1(octopus_grid: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(octopus_grid.clone().into_iter().take(100).sum())
+    }
+
+    fn part2(octopus_grid: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        // the iterator never ends, so a position is always found if the octopuses ever synchronize
+        let octopus_count = octopus_grid.0.len();
+        Ok(octopus_grid.clone().into_iter().position(|x| x == octopus_count).unwrap() + 1)
+    }
+}
+
+pub fn day11_main(file_data: &str) -> Result<(usize, usize), PuzzleError> {
+    let octopus_grid = Day11::parse(file_data)?;
+
+    let flash_count = Day11::part1(&octopus_grid)?;
     println!("[Part 1] After 100 steps, {} flashes occured!", flash_count);
-    let step_count = octopus_grid_iter.position(|x| x == (GRID_SIZE*GRID_SIZE) as u8).unwrap() + 1;
+    let step_count = Day11::part2(&octopus_grid)?;
     println!("[Part 2] First synchronization happens after {} steps!", step_count);
 
-    (flash_count, step_count)
+    Ok((flash_count, step_count))
 }
 
 
@@ -146,9 +143,7 @@ mod tests {
 
     #[test]
     fn it_works() {
-        let str_to_grid = |s: &str| {
-            s.chars().filter_map(|c| c.to_digit(10).map(|x| x as u8)).collect::<OctopusGrid>()
-        };
+        let str_to_grid = |s: &str| s.parse::<OctopusGrid>().unwrap();
 
         let test_data =
             "5483143223
@@ -174,13 +169,49 @@ mod tests {
             7922286866
             6789998766";
 
-        assert_eq!(day11_main(test_data), (1656, 195));
+        assert_eq!(day11_main(test_data).unwrap(), (1656, 195));
 
         let mut calculated_day100_grid = str_to_grid(test_data).into_iter();
         for _ in 1..=100 { calculated_day100_grid.next(); }
         let calculated_day100_grid = calculated_day100_grid.grid;
         let day100_grid = str_to_grid(day100_grid);
         assert_eq!(calculated_day100_grid, day100_grid);
+
+        let small_grid =
+            "11111
+            19991
+            19191
+            19991
+            11111";
+        let small_grid_step1 =
+            "34543
+            40004
+            50005
+            40004
+            34543";
+        let mut small_grid = str_to_grid(small_grid).into_iter();
+        assert_eq!(small_grid.next(), Some(9));
+        assert_eq!(small_grid.grid, str_to_grid(small_grid_step1));
+
+        // non-square grids, where flashes in the corners reach neighbours only inside the bounds
+        let wide_grid =
+            "91119
+            11111
+            91118";
+        let wide_grid_step1 =
+            "03230
+            44233
+            03229";
+        assert_eq!(day11_main(wide_grid).unwrap(), (154, 24));
+        let mut wide_grid = str_to_grid(wide_grid).into_iter();
+        assert_eq!(wide_grid.next(), Some(3));
+        assert_eq!(wide_grid.grid, str_to_grid(wide_grid_step1));
+
+        let tall_grid = "19\n11\n11\n11\n18";
+        assert_eq!(day11_main(tall_grid).unwrap(), (102, 17));
+        let mut tall_grid = str_to_grid(tall_grid).into_iter();
+        assert_eq!(tall_grid.next(), Some(1));
+        assert_eq!(tall_grid.grid, str_to_grid("30\n33\n22\n22\n29"));
     }
 
 }
\ No newline at end of file
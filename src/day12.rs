@@ -3,9 +3,10 @@ use std::iter;
 use std::fmt;
 use std::error::Error;
 use std::collections::{HashSet, HashMap};
+use crate::{Solution, PuzzleError, column_of};
 
 #[derive(Debug)]
-enum ParseNodeError {
+pub enum ParseNodeError {
     EmptyString, InvalidCase
 }
 impl fmt::Display for ParseNodeError {
@@ -34,14 +35,14 @@ impl str::FromStr for Node {
 }
 
 #[derive(Debug)]
-enum ParseCaveError {
-    NodeError((usize, ParseNodeError)), InvalidConnection(usize), MissingStart, MissingEnd
+pub enum ParseCaveError {
+    NodeError(ParseNodeError), InvalidConnection, MissingStart, MissingEnd
 }
 impl fmt::Display for ParseCaveError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ParseCaveError::NodeError(e) => write!(f, "ParseNodeError on line {}", e.0),
-            ParseCaveError::InvalidConnection(i) => write!(f, "invalid connection format on line {}", i),
+            ParseCaveError::NodeError(_) => write!(f, "invalid node"),
+            ParseCaveError::InvalidConnection => write!(f, "invalid connection format"),
             ParseCaveError::MissingStart => write!(f, "missing \"start\" node"),
             ParseCaveError::MissingEnd => write!(f, "mising \"end\" node"),
         }
@@ -50,54 +51,52 @@ impl fmt::Display for ParseCaveError {
 impl Error for ParseCaveError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            ParseCaveError::NodeError((_, ref e)) => Some(e),
+            ParseCaveError::NodeError(ref e) => Some(e),
             _ => None,
         }
     }
 }
-impl From<(usize, ParseNodeError)> for ParseCaveError {
-    fn from(err: (usize, ParseNodeError)) -> ParseCaveError {
+impl From<ParseNodeError> for ParseCaveError {
+    fn from(err: ParseNodeError) -> ParseCaveError {
         ParseCaveError::NodeError(err)
     }
 }
 #[derive(Debug)]
-struct CaveMap(HashMap<Node, HashSet<Node>>);
+pub struct CaveMap(HashMap<Node, HashSet<Node>>);
 impl iter::FromIterator<(Node, Node)> for CaveMap {
     fn from_iter<I: IntoIterator<Item = (Node, Node)>>(iter: I) -> Self {
         let mut cave = CaveMap(HashMap::new());
         for (node1, node2) in iter {
             if node1 == node2 { continue; }
-            cave.0.entry(node1.clone()).or_insert_with(HashSet::new).insert(node2.clone());
-            cave.0.entry(node2).or_insert_with(HashSet::new).insert(node1);
+            cave.0.entry(node1.clone()).or_default().insert(node2.clone());
+            cave.0.entry(node2).or_default().insert(node1);
         }
         cave
     }
 }
 impl str::FromStr for CaveMap {
-    type Err = ParseCaveError;
+    type Err = PuzzleError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let res = s.lines().enumerate().map(|(i, line)| {
+        let cave = s.lines().enumerate().map(|(i, line)| {
             let line_number = i + 1;
             let mut nodes = line.split('-');
             if nodes.clone().count() != 2 {
-                return Err(ParseCaveError::InvalidConnection(line_number));
+                return Err(PuzzleError::at_line(line_number, ParseCaveError::InvalidConnection));
             }
 
-            let node1 = nodes.next().unwrap().trim()
-                .parse::<Node>()
-                .map_err(|e| (line_number, e))?;
-            let node2 = nodes.next().unwrap().trim()
-                .parse::<Node>()
-                .map_err(|e| (line_number, e))?;
+            let parse_node = |s: &str| {
+                s.trim().parse::<Node>()
+                    .map_err(|e| PuzzleError::at(line_number, column_of(line, s), ParseCaveError::from(e)))
+            };
+            let node1 = parse_node(nodes.next().unwrap())?;
+            let node2 = parse_node(nodes.next().unwrap())?;
             Ok((node1, node2))
         })
-        .collect::<Result<CaveMap, _>>();
+        .collect::<Result<CaveMap, _>>()?;
 
-        if let Ok(ref cave) = res {
-            if !cave.0.contains_key(&Node::Start) { return Err(ParseCaveError::MissingStart); }
-            if !cave.0.contains_key(&Node::End)   { return Err(ParseCaveError::MissingEnd); }
-        }
-        res
+        if !cave.0.contains_key(&Node::Start) { return Err(PuzzleError::invalid(ParseCaveError::MissingStart)); }
+        if !cave.0.contains_key(&Node::End)   { return Err(PuzzleError::invalid(ParseCaveError::MissingEnd)); }
+        Ok(cave)
     }
 }
 impl CaveMap {
@@ -179,7 +178,7 @@ impl CaveMap {
                         _ => (),
                     }
                     // push all valid next paths onto the stack
-                    if second_visited == None {
+                    if second_visited.is_none() {
                         stack.extend(
                         self.0.get(node).unwrap().iter()
                                 .filter(|x|
@@ -208,17 +207,33 @@ enum StackElem<'a> {
     NextNode(&'a Node), StackDiv(&'a Node)
 }
 
-pub fn day12_main(file_data: &str) -> (u32, u32) {
-    let cave = file_data.parse::<CaveMap>().unwrap_or_else(|e| {
-        if let Some(src) = e.source() { panic!("Error parsing cave! : {},  caused by \"{}\"", e, src); }
-        else { panic!("Error parsing cave! : {}", e); }
-    });
-    let p1_path_count = cave.count_paths_p1();
+pub struct Day12;
+impl Solution for Day12 {
+    type Input = CaveMap;
+    type Answer1 = u32;
+    type Answer2 = u32;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        file_data.parse::<CaveMap>()
+    }
+
+    fn part1(cave: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(cave.count_paths_p1())
+    }
+
+    fn part2(cave: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        Ok(cave.count_paths_p2())
+    }
+}
+
+pub fn day12_main(file_data: &str) -> Result<(u32, u32), PuzzleError> {
+    let cave = Day12::parse(file_data)?;
+    let p1_path_count = Day12::part1(&cave)?;
     println!("[Part 1] The number of all possible paths is {}.", p1_path_count);
-    let p2_path_count = cave.count_paths_p2();
+    let p2_path_count = Day12::part2(&cave)?;
     println!("[Part 2] The number of all possible paths is {}.", p2_path_count);
 
-    (p1_path_count, p2_path_count)
+    Ok((p1_path_count, p2_path_count))
 }
 
 #[cfg(test)]
@@ -236,7 +251,7 @@ mod tests {
             A-end
             b-end";
 
-        assert_eq!(day12_main(test_data), (10, 36));
+        assert_eq!(day12_main(test_data).unwrap(), (10, 36));
     }
 
     #[test]
@@ -253,7 +268,7 @@ mod tests {
             kj-HN
             kj-dc";
 
-        assert_eq!(day12_main(test_data), (19, 103));
+        assert_eq!(day12_main(test_data).unwrap(), (19, 103));
     }
 
     #[test]
@@ -278,6 +293,6 @@ mod tests {
             pj-fs
             start-RW";
 
-        assert_eq!(day12_main(test_data), (226, 3509));
+        assert_eq!(day12_main(test_data).unwrap(), (226, 3509));
     }
 }
\ No newline at end of file
@@ -6,6 +6,7 @@ use std::error::Error;
 use std::num::ParseIntError;
 use std::collections::HashSet;
 use std::collections::BTreeSet;
+use crate::{Extra, Extras, Solution, PuzzleError};
 
 // ParsePointError
 #[derive(Debug)]
@@ -51,10 +52,7 @@ impl str::FromStr for Point {
 }
 impl PartialOrd for Point {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match self.1.cmp(&other.1) {
-            Ordering::Equal => Some(self.0.cmp(&other.0)),
-            x => Some(x),
-        }
+        Some(self.cmp(other))
     }
 }
 impl Ord for Point {
@@ -68,6 +66,7 @@ impl Ord for Point {
 
 // ParseFoldError
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 enum ParseFoldError {
     InvalidFormat, InvalidFoldAxis, InvalidNum(ParseIntError)
 }
@@ -102,7 +101,7 @@ enum Fold {
 impl str::FromStr for Fold {
     type Err = ParseFoldError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut tokens = s.trim().split_whitespace();
+        let mut tokens = s.split_whitespace();
         if (Some("fold"), Some("along")) != (tokens.next(), tokens.next()) {
             return Err(ParseFoldError::InvalidFormat);
         }
@@ -130,7 +129,8 @@ impl fmt::Display for Fold {
 
 
 // Paper
-struct Paper {
+#[derive(Clone)]
+pub struct Paper {
     points: BTreeSet<Point>,
     size: Point
 }
@@ -226,67 +226,84 @@ impl fmt::Display for Paper {
 
 
 
-pub fn day13_main(file_data: &str) -> u16 {
-    let block_delimiter;
-    if file_data.chars().nth(file_data.lines().next()
-        .unwrap_or_else(|| panic!("File is empty!")).len()).unwrap() == '\r' {
-            block_delimiter = "\r\n\r\n";
+pub struct Manual {
+    paper: Paper,
+    folds: Vec<Fold>,
+    first_fold_line: usize,
+}
+impl Manual {
+    // applies the first `fold_count` folds to a copy of the paper
+    fn fold(&self, fold_count: usize) -> Result<Paper, PuzzleError> {
+        let mut paper = self.paper.clone();
+        for (i, fold) in self.folds.iter().take(fold_count).enumerate() {
+            if !paper.fold_paper(fold) {
+                return Err(PuzzleError::at_line(self.first_fold_line + i, format!("\"{}\" is outside the paper", fold)));
+            }
         }
-    else { block_delimiter = "\n\n"; }
+        Ok(paper)
+    }
+}
 
-    let mut file_blocks = file_data.split(block_delimiter);
-    let mut paper = file_blocks.next().unwrap_or("").lines().enumerate()
-        .map(|(i, line)| {
-            line.parse::<Point>().unwrap_or_else(|e| {
-                match e.source() {
-                    None => panic!("Error parsing point on line {}! :\nParsePointError{{ {} }}", i+1, e),
-                    Some(src) =>
-                        panic!("Error parsing point on line {}! :\nParsePointError{{ {} }}, \
-                        caused by ParseIntError{{ {} }}", i+1, e, src),
-                }
-            })
-        })
-        .collect::<Paper>();
-    let mut folds = file_blocks.next().unwrap_or("").lines().map(str::parse::<Fold>);
+pub struct Day13;
+impl Solution for Day13 {
+    type Input = Manual;
+    type Answer1 = u16;
+    type Answer2 = Paper;
 
-    // Part 1
-    let first_fold = folds.next().unwrap_or_else(|| panic!("No folds to do!"));
-    match first_fold {
-        Ok(fold) => {
-            if !paper.fold_paper(&fold) {
-                panic!("Error: Fold 1 {{ {} }} is invalid!", fold);
-            }
-        },
-        Err(e) => {
-            if let Some(src) = e.source() {
-                panic!("Error parsing fold 1! : {}, caused by ParseIntError{{ {} }}", e, src);
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        let first_line = file_data.lines().next().ok_or_else(|| PuzzleError::invalid("the input is empty"))?;
+        let block_delimiter = if file_data.chars().nth(first_line.len()) == Some('\r') {
+                "\r\n\r\n"
             }
-            else { panic!("Error parsing fold 1! : {}", e); }
-        },
+        else { "\n\n" };
+
+        let mut file_blocks = file_data.split(block_delimiter);
+        let point_block = file_blocks.next().unwrap_or("");
+        let paper = point_block.lines().enumerate()
+            .map(|(i, line)| {
+                line.parse::<Point>().map_err(|e| PuzzleError::at_line(i+1, e))
+            })
+            .collect::<Result<Paper, _>>()?;
+
+        // the folds start after the points and an empty line
+        let first_fold_line = point_block.lines().count() + 2;
+        let folds = file_blocks.next().unwrap_or("").lines().enumerate()
+            .map(|(i, line)| {
+                line.parse::<Fold>().map_err(|e| PuzzleError::at_line(first_fold_line + i, e))
+            })
+            .collect::<Result<Vec<Fold>, _>>()?;
+        if folds.is_empty() { return Err(PuzzleError::invalid("there are no folds to do")); }
+
+        Ok(Manual { paper, folds, first_fold_line })
+    }
+
+    fn part1(manual: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(manual.fold(1)?.count_points())
+    }
+
+    fn part2(manual: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        manual.fold(manual.folds.len())
+    }
+
+    fn part2_extras(paper: &Self::Answer2) -> Extras {
+        // the folded text row by row, so it can be shown without splitting the answer
+        let rows = paper.to_string().lines().map(|row| Extra::Text(row.to_owned())).collect();
+        vec![("rows", Extra::List(rows)), ("dots", Extra::Int(paper.count_points().into()))]
     }
-    let part1_point_count = paper.count_points();
+}
+
+pub fn day13_main(file_data: &str) -> Result<(u16, String), PuzzleError> {
+    let manual = Day13::parse(file_data)?;
+
+    // Part 1
+    let part1_point_count = Day13::part1(&manual)?;
     println!("[Part 1] The number of points after 1 fold is {}.", part1_point_count);
 
     // Part 2
-    for (i, res) in folds.enumerate() {
-        let i = i+2;
-        match res {
-            Ok(fold) => {
-                if !paper.fold_paper(&fold) {
-                    panic!("Error: Fold {} {{ {} }} is invalid!", i, fold);
-                }
-            },
-            Err(e) => {
-                if let Some(src) = e.source() {
-                    panic!("Error parsing fold {}! : {}, caused by ParseIntError{{ {} }}", i, e, src);
-                }
-                else { panic!("Error parsing fold {}! : {}", i, e); }
-            },
-        }
-    }
-    println!("After completing all folds, paper = \n{}", paper);
+    let folded_paper = Day13::part2(&manual)?.to_string();
+    println!("After completing all folds, paper = \n{}", folded_paper);
 
-    part1_point_count
+    Ok((part1_point_count, folded_paper))
 }
 
 
@@ -319,6 +336,13 @@ mod tests {
             fold along y=7
             fold along x=5";
 
-        assert_eq!(day13_main(test_data), 17);
+        assert_eq!(day13_main(test_data).unwrap().0, 17);
+
+        let paper = Day13::part2(&Day13::parse(test_data).unwrap()).unwrap();
+        let extras = Day13::part2_extras(&paper);
+        assert_eq!(extras[0].0, "rows");
+        assert_eq!(extras[0].1, Extra::List(["#####", "#...#", "#...#", "#...#", "#####", ".....", "....."]
+            .iter().map(|row| Extra::Text(row.to_string())).collect()));
+        assert_eq!(extras[1], ("dots", Extra::Int(16)));
     }
 }
\ No newline at end of file
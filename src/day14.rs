@@ -4,13 +4,14 @@ use std::iter;
 use std::hash::Hash;
 use std::error::Error;
 use std::collections::HashMap;
+use crate::{Solution, PuzzleError};
 
 type Element = char;
 type Pair = [char; 2];
 type RuleMap = HashMap<[char; 2], Element>;
 
 #[derive(Debug)]
-enum ParseRuleError {
+pub enum ParseRuleError {
     FormatError, InvalidPattern, InvalidElement
 }
 impl fmt::Display for ParseRuleError {
@@ -45,6 +46,7 @@ impl str::FromStr for Rule {
     }
 }
 
+#[derive(Clone)]
 struct CountMap<T: Eq + Hash>(HashMap<T, u64>);
 impl<T: Eq + Hash> CountMap<T> {
     fn new() -> Self {
@@ -62,12 +64,31 @@ impl<T: Eq + Hash> iter::Extend<(T, u64)> for CountMap<T> {
     }
 }
 
+#[derive(Debug)]
+pub enum ParseTemplateError {
+    EmptyTemplate, InvalidElement(char)
+}
+impl fmt::Display for ParseTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTemplateError::EmptyTemplate => write!(f, "the polymer template is empty"),
+            ParseTemplateError::InvalidElement(c) => write!(f, "invalid element {:?} in the polymer template", c),
+        }
+    }
+}
+impl Error for ParseTemplateError {}
+
+#[derive(Clone)]
 struct Template {
     pair_count: CountMap<Pair>, element_count: CountMap<Element>
 }
 impl str::FromStr for Template {
-    type Err = ();
+    type Err = ParseTemplateError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() { return Err(ParseTemplateError::EmptyTemplate); }
+        if let Some(c) = s.chars().find(|c| c.is_whitespace()) { return Err(ParseTemplateError::InvalidElement(c)); }
+
         let mut pair_count = CountMap::new();
         let mut element_count = CountMap::new();
 
@@ -81,33 +102,41 @@ impl str::FromStr for Template {
 }
 
 #[derive(Debug)]
-enum ParsePolymerFinderError {
-    FormatError, InvalidRule((usize, ParseRuleError)), DuplicatePattern
+pub enum ParsePolymerFinderError {
+    FormatError, InvalidTemplate(ParseTemplateError), InvalidRule(ParseRuleError), DuplicatePattern
 }
 impl fmt::Display for ParsePolymerFinderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ParsePolymerFinderError::FormatError => write!(f, "invalid instructions format"),
             ParsePolymerFinderError::DuplicatePattern => write!(f, "duplicate pattern found"),
-            ParsePolymerFinderError::InvalidRule((i, _)) => write!(f, "insertion rule {} is invalid", i),
+            ParsePolymerFinderError::InvalidTemplate(_) => write!(f, "polymer template is invalid"),
+            ParsePolymerFinderError::InvalidRule(_) => write!(f, "insertion rule is invalid"),
         }
     }
 }
-impl From<(usize, ParseRuleError)> for ParsePolymerFinderError {
-    fn from(e: (usize, ParseRuleError)) -> Self {
+impl From<ParseTemplateError> for ParsePolymerFinderError {
+    fn from(e: ParseTemplateError) -> Self {
+        ParsePolymerFinderError::InvalidTemplate(e)
+    }
+}
+impl From<ParseRuleError> for ParsePolymerFinderError {
+    fn from(e: ParseRuleError) -> Self {
         ParsePolymerFinderError::InvalidRule(e)
     }
 }
 impl Error for ParsePolymerFinderError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            ParsePolymerFinderError::InvalidRule((_, e)) => Some(e),
+            ParsePolymerFinderError::InvalidTemplate(e) => Some(e),
+            ParsePolymerFinderError::InvalidRule(e) => Some(e),
             _ => None,
         }
     }
 }
 
-struct PolymerFinder {
+#[derive(Clone)]
+pub struct PolymerFinder {
     template: Template, rules: RuleMap
 }
 impl PolymerFinder {
@@ -126,14 +155,23 @@ impl PolymerFinder {
         });
         self.template.pair_count.extend(temp.0);
     }
+
+    // difference between the counts of the most and least common elements
+    fn count_difference(&self) -> u64 {
+        let max_count = self.template.element_count.0.values().max().unwrap();
+        let min_count = self.template.element_count.0.values().min().unwrap();
+        max_count - min_count
+    }
 }
 impl str::FromStr for PolymerFinder {
-    type Err = ParsePolymerFinderError;
+    type Err = PuzzleError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid_template = |e: ParseTemplateError| PuzzleError::at_line(1, ParsePolymerFinderError::from(e));
+        let first_line = s.lines().next().ok_or_else(|| PuzzleError::invalid(ParsePolymerFinderError::FormatError))?;
         let section_delimiter =
-            match s.chars().nth(s.lines().next().ok_or(ParsePolymerFinderError::FormatError)?.len()) {
+            match s.chars().nth(first_line.len()) {
                 None => return Ok(PolymerFinder {
-                    template: s.parse().unwrap(), rules: RuleMap::new()
+                    template: s.parse().map_err(invalid_template)?, rules: RuleMap::new()
                 }),
                 Some('\r') => "\r\n\r\n",
                 _ => "\n\n",
@@ -141,46 +179,63 @@ impl str::FromStr for PolymerFinder {
 
         let mut sections = s.split(section_delimiter);
         if let (Some(template), Some(rules), None) = (sections.next(),sections.next(),sections.next()) {
-            let rule_map = rules.lines().enumerate()
-                .map(|(i, s)| {
-                    let rule = s.parse::<Rule>().map_err(|e| (i+1, e))?;
-                    Ok((rule.0, rule.1))
-                })
-                .collect::<Result<RuleMap, ParsePolymerFinderError>>()?;
-            if rules.lines().count() != rule_map.len() { return Err(ParsePolymerFinderError::DuplicatePattern); }
-            let template = template.parse().unwrap();
+            let template = template.parse().map_err(invalid_template)?;
+
+            // the rules start after the template and an empty line
+            let first_rule_line = 3;
+            let mut rule_map = RuleMap::new();
+            for (i, s) in rules.lines().enumerate() {
+                let rule = s.parse::<Rule>()
+                    .map_err(|e| PuzzleError::at_line(first_rule_line + i, ParsePolymerFinderError::from(e)))?;
+                if rule_map.insert(rule.0, rule.1).is_some() {
+                    return Err(PuzzleError::at_line(first_rule_line + i, ParsePolymerFinderError::DuplicatePattern));
+                }
+            }
 
             Ok(PolymerFinder { template, rules: rule_map })
         }
-        else { Err(ParsePolymerFinderError::FormatError) }
+        else { Err(PuzzleError::invalid(ParsePolymerFinderError::FormatError)) }
     }
 }
 
 
-pub fn day14_main(file_data: &str) -> (u64, u64) {
-    let mut polymer_finder = file_data.parse::<PolymerFinder>()
-        .unwrap_or_else(|e| {
-            if let Some(src) = e.source() {
-                panic!("Error parsing instructions! : {}, caused by \"{}\"", e, src);
-            }
-            else { panic!("Error parsing instructions! : {}", e); }
-        });
+pub struct Day14;
+impl Solution for Day14 {
+    type Input = PolymerFinder;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        file_data.parse::<PolymerFinder>()
+    }
+
+    fn part1(polymer_finder: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        let mut polymer_finder = polymer_finder.clone();
+        for _ in 1..=10 { polymer_finder.apply_insertion(); }
+        Ok(polymer_finder.count_difference())
+    }
+
+    fn part2(polymer_finder: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        let mut polymer_finder = polymer_finder.clone();
+        for _ in 1..=40 { polymer_finder.apply_insertion(); }
+        Ok(polymer_finder.count_difference())
+    }
+}
+
+pub fn day14_main(file_data: &str) -> Result<(u64, u64), PuzzleError> {
+    let mut polymer_finder = Day14::parse(file_data)?;
 
     // Part 1
     for _ in 1..=10 { polymer_finder.apply_insertion(); }
-    let max_count = polymer_finder.template.element_count.0.values().max().unwrap();
-    let min_count = polymer_finder.template.element_count.0.values().min().unwrap();
-    let part1_answer = max_count - min_count;
+    let part1_answer = polymer_finder.count_difference();
     println!("[Part 1] After 10 insertions, The differnce is {}.", part1_answer);
 
     // Part 2
     for _ in 1..=30 { polymer_finder.apply_insertion(); }
-    let max_count = polymer_finder.template.element_count.0.values().max().unwrap();
-    let min_count = polymer_finder.template.element_count.0.values().min().unwrap();
-    let part2_answer = max_count - min_count;
+    let part2_answer = polymer_finder.count_difference();
     println!("[Part 2] After 40 insertions, The differnce is {}.", part2_answer);
 
-    (part1_answer, part2_answer)
+    Ok((part1_answer, part2_answer))
 }
 
 
@@ -210,6 +265,6 @@ mod tests {
             CC -> N
             CN -> C";
 
-        assert_eq!(day14_main(test_data), (1588, 2188189693529));
+        assert_eq!(day14_main(test_data).unwrap(), (1588, 2188189693529));
     }
 }
\ No newline at end of file
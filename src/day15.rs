@@ -1,34 +1,18 @@
-use std::str;
 use std::fmt;
 use core::ops;
 use std::error::Error;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
+use crate::grid::{Grid, Point};
+use crate::{Solution, PuzzleError};
 
 
-const SIZE: usize = 100;
 const MIN_RISK: u16 = 1;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Point(usize, usize);
-impl Point {
-    fn get_neighbours(&self, grid_size: Point) -> [Option<Point>; 4] {
-        let up = (self.0 > 0).then(|| Point(self.0-1, self.1));
-        let down = (self.0 < grid_size.0-1).then(|| Point(self.0+1, self.1));
-        let left = (self.1 > 0).then(|| Point(self.0, self.1-1));
-        let right = (self.1 < grid_size.1-1).then(|| Point(self.0, self.1+1));
-        [up, down, left, right]
-    }
-}
-impl<T> ops::Index<Point> for Grid<T> {
-    type Output = T;
+impl ops::Index<Point> for CavernMap {
+    type Output = u8;
     fn index(&self, index: Point) -> &Self::Output {
-        &self.grid[index.0 * self.size.1 + index.1]
-    }
-}
-impl<T> ops::IndexMut<Point> for Grid<T> {
-    fn index_mut(&mut self, index: Point) -> &mut Self::Output {
-        &mut self.grid[index.0 * self.size.1 + index.1]
+        &self.0[index]
     }
 }
 
@@ -39,10 +23,7 @@ struct PQueueElem {
 // flip comparison order so that BinaryHeap implemented as min-heap
 impl PartialOrd for PQueueElem {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match other.hcost.cmp(&self.hcost) {
-            Ordering::Equal => Some(other.cost.cmp(&self.cost)),
-            x => Some(x)
-        }
+        Some(self.cmp(other))
     }
 }
 impl Ord for PQueueElem {
@@ -55,90 +36,69 @@ impl Ord for PQueueElem {
 }
 
 #[derive(Debug)]
-enum ParseCavernError {
-    InvalidSize, InvalidRisk(Point)
+pub enum ParseCavernError {
+    InvalidRisk(char)
 }
 impl fmt::Display for ParseCavernError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ParseCavernError::InvalidSize => write!(f, "cavern size and string length don't match"),
-            ParseCavernError::InvalidRisk(p) =>
-                write!(f, "invalid risk level at position ({}, {})", p.0+1, p.1+1),
+            ParseCavernError::InvalidRisk(c) => write!(f, "invalid risk level '{}', expected a digit", c),
         }
     }
 }
 impl Error for ParseCavernError {}
 
-struct Grid<T> {
-    grid: Vec<T>, size: Point
-}
-impl<T: Clone> Grid<T> {
-    fn new(size: Point, init_val: T) -> Self {
-        Grid { grid: vec![init_val; size.0*size.1], size }
-    }
-}
-type CavernMap = Grid<u8>;
+#[derive(Debug, Clone)]
+pub struct CavernMap(Grid<u8>);
 impl CavernMap {
-    fn from_str(s: &str, size: Point) -> Result<Self, ParseCavernError> {
-        let count = size.0 * size.1;
-        let mut cavern = CavernMap::new(size, 0);
-        let mut i = 0;
-        let risk_chars = s.lines().flat_map(|line| line.trim().chars());
-        for c in risk_chars {
-            let risk = c.to_digit(10)
-                .ok_or_else(|| ParseCavernError::InvalidRisk(Point((i/size.1)*size.1, i%size.1)))? as u8;
-            if i > count-1 { return Err(ParseCavernError::InvalidSize); }
-            cavern.grid[i] = risk;
-            i += 1;
-        }
-        if i != count { return Err(ParseCavernError::InvalidSize); }
-        Ok(cavern)
+    fn from_str(s: &str) -> Result<Self, PuzzleError> {
+        let grid = Grid::parse_with(s, |c| {
+            c.to_digit(10).map(|x| x as u8).ok_or(ParseCavernError::InvalidRisk(c))
+        })?;
+        Ok(CavernMap(grid))
     }
 
-    fn expand_map(&self) -> Self {
-        let mut new_cavern = CavernMap::new(Point(5*self.size.0, 5*self.size.1), 0);
-        for i in 0..new_cavern.size.0 {
-            for j in 0..new_cavern.size.1 {
-                let base_index = Point(i % self.size.0, j % self.size.1);
-                let risk_offset = ((i-base_index.0) + (j-base_index.1)) / SIZE;
-                let mut risk = self[base_index] + risk_offset as u8;
-                while risk > 9 {
-                    risk -= 9;
-                }
-                new_cavern[Point(i, j)] = risk;
+    // the top left and bottom right positions, which are the endpoints of the path
+    pub fn corners(&self) -> (Point, Point) {
+        ((0, 0), (self.0.height()-1, self.0.width()-1))
+    }
+
+    pub fn expand_map(&self) -> Self {
+        let (height, width) = (self.0.height(), self.0.width());
+        let mut new_cavern = Grid::new(5*height, 5*width, 0);
+        for (i, j) in new_cavern.positions() {
+            let risk_offset = i / height + j / width;
+            let mut risk = self[(i % height, j % width)] + risk_offset as u8;
+            while risk > 9 {
+                risk -= 9;
             }
+            new_cavern[(i, j)] = risk;
         }
-        new_cavern
+        CavernMap(new_cavern)
     }
 
     // find minimum cost path using A* search algorithm
-    fn find_min_risk(&self, start: Point, end: Point) -> Result<Option<u16>, ()> {
-        let is_in_bounds = |p: Point| -> bool {
-            p.0 < self.size.0 && p.1 < self.size.1
-        };
-        if !is_in_bounds(start) || !is_in_bounds(end) { return Err(()); }
+    pub fn find_min_risk(&self, start: Point, end: Point) -> Result<Option<u16>, PuzzleError> {
+        if !self.0.contains(start) || !self.0.contains(end) { return Err(endpoints_outside_cavern()); }
 
         let estimate_cost = |p: Point| -> u16 {
             // use manhattan distance (modified to prevent overestimation) as heuristic cost estimate
             MIN_RISK * (my_abs_diff(end.0, p.0) + my_abs_diff(end.1, p.1)) as u16
         };
-        let mut min_costs = Grid::new(self.size, None);
+        let mut min_costs = Grid::new(self.0.height(), self.0.width(), None);
         min_costs[start] = Some(0);
 
         let mut pqueue = BinaryHeap::new();
         pqueue.push(PQueueElem {
             hcost: estimate_cost(start), cost: 0, pos: start
         });
-        let mut run_count = 0;
         while let Some(elem) = pqueue.pop() {
             if elem.pos == end { return Ok(min_costs[elem.pos]); }
 
             // don't bother anymore if an even cheaper path to elem.pos has already been found
             if min_costs[elem.pos].unwrap() < elem.cost { continue; }
 
-            for p in elem.pos.get_neighbours(self.size) {
-                if p.is_none() { continue; }
-                let p = p.unwrap();
+            for p in self.0.neighbours4(elem.pos) {
                 let new_cost = elem.cost + self[p] as u16;
                 if min_costs[p].is_none() || new_cost < min_costs[p].unwrap() {
                     min_costs[p] = Some(new_cost);
@@ -151,18 +111,15 @@ impl CavernMap {
         Ok(None)
     }
 
-    fn bidirectional_astar(&self, start: Point, end: Point) -> Result<Option<u16>, ()> {
-        let is_in_bounds = |p: Point| -> bool {
-            p.0 < self.size.0 && p.1 < self.size.1
-        };
-        if !is_in_bounds(start) || !is_in_bounds(end) { return Err(()); }
+    pub fn bidirectional_astar(&self, start: Point, end: Point) -> Result<Option<u16>, PuzzleError> {
+        if !self.0.contains(start) || !self.0.contains(end) { return Err(endpoints_outside_cavern()); }
 
         let estimate_cost = |p1: Point, p2:Point| -> u16 {
             // use manhattan distance (modified to prevent overestimation) as heuristic cost estimate
             MIN_RISK * (my_abs_diff(p1.0, p2.0) + my_abs_diff(p1.1, p2.1)) as u16
         };
 
-        let mut min_costs = Grid::new(self.size, (None, None));
+        let mut min_costs = Grid::new(self.0.height(), self.0.width(), (None, None));
         min_costs[start] = (Some(0), None);
         min_costs[end] = (None, Some(self[end] as u16));
 
@@ -182,10 +139,8 @@ impl CavernMap {
                 if u.cost + v.cost > d + mu_offset { return Ok(mu); }
             }
 
-            if !(min_costs[u.pos].0.unwrap() < u.cost) {
-                for p in u.pos.get_neighbours(self.size) {
-                    if p.is_none() { continue; }
-                    let p = p.unwrap();
+            if min_costs[u.pos].0.unwrap() >= u.cost {
+                for p in self.0.neighbours4(u.pos) {
                     let new_cost = u.cost + self[p] as u16;
                     if min_costs[p].0.is_none() || new_cost < min_costs[p].0.unwrap() {
                         min_costs[p].0 = Some(new_cost);
@@ -204,10 +159,8 @@ impl CavernMap {
                 }
             }
 
-            if !(min_costs[v.pos].1.unwrap() < v.cost) {
-                for p in v.pos.get_neighbours(self.size) {
-                    if p.is_none() { continue; }
-                    let p = p.unwrap();
+            if min_costs[v.pos].1.unwrap() >= v.cost {
+                for p in self.0.neighbours4(v.pos) {
                     let new_cost = v.cost + self[p] as u16;
                     if min_costs[p].1.is_none() || new_cost < min_costs[p].1.unwrap() {
                         min_costs[p].1 = Some(new_cost);
@@ -236,15 +189,41 @@ fn my_abs_diff<T: std::ops::Sub<Output = T> + Ord>(a: T, b: T) -> T {
 }
 
 
-pub fn day15_main(file_data: &str) -> (Option<u16>, Option<u16>) {
+pub struct Day15;
+impl Solution for Day15 {
+    type Input = CavernMap;
+    type Answer1 = u16;
+    type Answer2 = u16;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        CavernMap::from_str(file_data)
+    }
+
+    fn part1(cavern: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        let (start, end) = cavern.corners();
+        cavern.find_min_risk(start, end)?.ok_or_else(no_path)
+    }
+
+    fn part2(cavern: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        let new_cavern = cavern.expand_map();
+        let (start, end) = new_cavern.corners();
+        new_cavern.bidirectional_astar(start, end)?.ok_or_else(no_path)
+    }
+}
+
+fn endpoints_outside_cavern() -> PuzzleError {
+    PuzzleError::no_solution("one or more endpoints are not within the cavern")
+}
+
+fn no_path() -> PuzzleError {
+    PuzzleError::no_solution("could not find any path connecting the endpoints")
+}
+
+pub fn day15_main(file_data: &str) -> Result<(Option<u16>, Option<u16>), PuzzleError> {
     // Part 1
-    let cavern = CavernMap::from_str(file_data, Point(SIZE, SIZE))
-        .unwrap_or_else(|e| {
-            panic!("Error parsing cavern! : {}", e);
-        });
-    let (start, end) = (Point(0, 0), Point(SIZE-1, SIZE-1));
-    let part1_answer = cavern.find_min_risk(start, end)
-        .unwrap_or_else(|_| panic!("[Part 1] Error: One or more endpoints not within cavern!"));
+    let cavern = Day15::parse(file_data)?;
+    let (start, end) = cavern.corners();
+    let part1_answer = cavern.find_min_risk(start, end)?;
     match part1_answer {
         Some(x) => println!("[Part 1] The lowest total risk possible is {}.", x),
         None => println!("[Part 1] Could not find any path connecting the endpoints."),
@@ -252,15 +231,14 @@ pub fn day15_main(file_data: &str) -> (Option<u16>, Option<u16>) {
 
     // Part 2
     let new_cavern = cavern.expand_map();
-    let (start, end) = (Point(0, 0), Point(5*SIZE-1, 5*SIZE-1));
-    let part2_answer = new_cavern.bidirectional_astar(start, end)
-        .unwrap_or_else(|_| panic!("[Part 2] Error: One or more endpoints not within cavern!"));
+    let (start, end) = new_cavern.corners();
+    let part2_answer = new_cavern.bidirectional_astar(start, end)?;
     match part2_answer {
         Some(x) => println!("[Part 2] The lowest total risk possible is {}.", x),
         None => println!("[Part 2] Could not find any path connecting the endpoints."),
     }
 
-    (part1_answer, part2_answer)
+    Ok((part1_answer, part2_answer))
 }
 
 
@@ -281,6 +259,11 @@ mod tests {
             3125421639
             1293138521
             2311944581";
-        assert_eq!(day15_main(test_data), (Some(40), Some(315)));
+        assert_eq!(day15_main(test_data).unwrap(), (Some(40), Some(315)));
+
+        // non-square caverns
+        let cavern = Day15::parse("116\n138").unwrap();
+        assert_eq!(Day15::part1(&cavern).unwrap(), 12);
+        assert_eq!(Day15::part2(&cavern).unwrap(), Day15::part2(&Day15::parse("11\n13\n68").unwrap()).unwrap());
     }
 }
\ No newline at end of file
@@ -1,12 +1,23 @@
-use std::panic;
+use std::fmt;
+use std::error::Error;
 use std::convert::TryFrom;
+use crate::{Solution, PuzzleError};
 
 
-struct Packet {
+pub struct Packet {
     version: u8,
     packet_data: PacketData,
 }
 impl Packet {
+    fn version_sum(&self) -> u16 {
+        let subpacket_sum = match &self.packet_data {
+            PacketData::Literal { .. } => 0,
+            PacketData::Operator { subpackets, .. } => subpackets.iter().map(Packet::version_sum).sum(),
+        };
+        self.version as u16 + subpacket_sum
+    }
+
+    // the number of subpackets is checked when parsing, so evaluating an operator never fails
     fn evaluate(&self) -> u64 {
         match &self.packet_data {
             PacketData::Literal { data } => *data,
@@ -15,32 +26,29 @@ impl Packet {
                     .iter()
                     .map(Packet::evaluate);
 
-                panic::catch_unwind(|| {
-                    match operation {
-                        Operation::Sum => subpacket_data_iter.sum(),
-                        Operation::Product => subpacket_data_iter.product(),
-                        Operation::Min => subpacket_data_iter.min().unwrap(),
-                        Operation::Max => subpacket_data_iter.max().unwrap(),
-                        op => {
-                            let first = subpacket_data_iter.next().unwrap();
-                            let second = subpacket_data_iter.next().unwrap();
-                            assert!(subpacket_data_iter.next().is_none());
-                            let satisfied = match op {
-                                Operation::Greater => first > second,
-                                Operation::Less => first < second,
-                                Operation::Eq  => first == second,
-                                _ => panic!(),
-                            };
-                            if satisfied { 1 } else { 0 }
-                        }
+                match operation {
+                    Operation::Sum => subpacket_data_iter.sum(),
+                    Operation::Product => subpacket_data_iter.product(),
+                    Operation::Min => subpacket_data_iter.min().unwrap(),
+                    Operation::Max => subpacket_data_iter.max().unwrap(),
+                    op => {
+                        let first = subpacket_data_iter.next().unwrap();
+                        let second = subpacket_data_iter.next().unwrap();
+                        let satisfied = match op {
+                            Operation::Greater => first > second,
+                            Operation::Less => first < second,
+                            Operation::Eq  => first == second,
+                            _ => unreachable!(),
+                        };
+                        if satisfied { 1 } else { 0 }
                     }
-                }).unwrap_or_else(|_| panic!("incorrect number of subpackets to perform {:?}", operation))
+                }
             },
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum Operation {
     Sum, Product, Min, Max, Greater, Less, Eq
 }
@@ -59,6 +67,14 @@ impl TryFrom<u8> for Operation {
         }
     }
 }
+impl Operation {
+    fn accepts_subpacket_count(&self, count: usize) -> bool {
+        match self {
+            Operation::Greater | Operation::Less | Operation::Eq => count == 2,
+            _ => count > 0,
+        }
+    }
+}
 
 enum PacketData {
     Literal {
@@ -70,32 +86,82 @@ enum PacketData {
     },
 }
 
+#[derive(Debug)]
+enum ParsePacketErrorKind {
+    InvalidHexDigit(char), UnexpectedEnd, LiteralTooLong, InvalidSubpacketCount(Operation)
+}
+impl fmt::Display for ParsePacketErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePacketErrorKind::InvalidHexDigit(c) => write!(f, "invalid hex digit '{}'", c),
+            ParsePacketErrorKind::UnexpectedEnd => write!(f, "unexpected end of packet"),
+            ParsePacketErrorKind::LiteralTooLong => write!(f, "literal value has more than 16 hex digits"),
+            ParsePacketErrorKind::InvalidSubpacketCount(op) =>
+                write!(f, "incorrect number of subpackets to perform {:?}", op),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParsePacketError {
+    pos: usize,
+    kind: ParsePacketErrorKind,
+}
+impl fmt::Display for ParsePacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at index {}", self.kind, self.pos)
+    }
+}
+impl Error for ParsePacketError {}
+
 #[derive(Clone, Copy)]
 struct BitString<'a> {
+    input: &'a str,
     str: &'a str,
     offset: u8,
-    version_number_sum: u16,
 }
 impl<'a> BitString<'a> {
-    fn advance_by(&mut self, offset: usize) {
+    fn new(input: &'a str) -> Self {
+        BitString { input, str: input, offset: 0 }
+    }
+
+    // index of the current hex digit within the input
+    fn pos(&self) -> usize {
+        self.input.len() - self.str.len()
+    }
+
+    fn error(&self, kind: ParsePacketErrorKind) -> ParsePacketError {
+        ParsePacketError { pos: self.pos(), kind }
+    }
+
+    fn advance_by(&mut self, offset: usize) -> Result<(), ParsePacketError> {
         let total_offset = (self.offset as usize) + offset;
+        if total_offset / 4 > self.str.len() {
+            return Err(ParsePacketError { pos: self.input.len(), kind: ParsePacketErrorKind::UnexpectedEnd });
+        }
         self.offset = (total_offset % 4) as u8;
         self.str = &self.str[(total_offset / 4)..];
+        Ok(())
     }
 
-    fn peek_bits_with_u16(&mut self, num_of_bits: u8) -> Result<u16, char> {
+    fn peek_bits_with_u16(&mut self, num_of_bits: u8) -> Result<u16, ParsePacketError> {
         assert!(num_of_bits < 17, "num_of_bits must be at most 16");
         if num_of_bits == 0 { return Ok(0); }
-        let len = 1 + (self.offset + num_of_bits - 1) / 4;  // round-up integer division
+        let len = (1 + (self.offset + num_of_bits - 1) / 4) as usize;  // round-up integer division
         let mask = 0xFFFFFFFF >> (32 - num_of_bits);
-        let bits = (u32::from_str_radix(&self.str[0..(len as usize)], 16)
-            .map_err(|_| {
-                (&self.str[0..5]).chars().find(|c| {
-                    !matches!(c, 'A'..='F' | '0'..='9')
-                })
-                .unwrap()
-            })?
-            >> (4*len - num_of_bits - self.offset))
+        let hex_digits = match self.str.get(0..len) {
+            Some(s) if s.chars().all(|c| c.is_ascii_hexdigit()) => s,
+            _ => {
+                let invalid_digit = self.str.char_indices().take(len).find(|(_, c)| !c.is_ascii_hexdigit());
+                return Err(match invalid_digit {
+                    Some((j, c)) => ParsePacketError {
+                        pos: self.pos() + j, kind: ParsePacketErrorKind::InvalidHexDigit(c)
+                    },
+                    None => ParsePacketError { pos: self.input.len(), kind: ParsePacketErrorKind::UnexpectedEnd },
+                });
+            }
+        };
+        let bits = (u32::from_str_radix(hex_digits, 16).unwrap() >> (4*len - num_of_bits as usize - self.offset as usize))
             & mask;
         Ok(bits as u16)
     }
@@ -106,18 +172,23 @@ impl<'a> BitString<'a> {
 }
 
 impl<'a> TryFrom<&mut BitString<'a>> for Packet {
-    type Error = char;
+    type Error = ParsePacketError;
     fn try_from(value: &mut BitString<'a>) -> Result<Self, Self::Error> {
+        let start = *value;
         let (version, type_id, length_type_id) = parse_header(value)?;
         let packet_data = parse_packet_data(value, type_id, length_type_id)?;
-        value.version_number_sum += version as u16;
+        if let PacketData::Operator { operation, subpackets } = &packet_data {
+            if !operation.accepts_subpacket_count(subpackets.len()) {
+                return Err(start.error(ParsePacketErrorKind::InvalidSubpacketCount(*operation)));
+            }
+        }
         Ok(Packet {
             version, packet_data
         })
     }
 }
 
-fn parse_header(input: &mut BitString) -> Result<(u8, u8, u8), char> {
+fn parse_header(input: &mut BitString) -> Result<(u8, u8, u8), ParsePacketError> {
     const ONE_BIT_MASK: u16 = 0x1;
     const THREE_BIT_MASK: u16 = 0x7;
     let bits = input.peek_bits_with_u16(7)?;
@@ -125,12 +196,12 @@ fn parse_header(input: &mut BitString) -> Result<(u8, u8, u8), char> {
     let version = (bits >> 4) & THREE_BIT_MASK;
     let type_id = (bits >> 1) & THREE_BIT_MASK;
     let type_length_id = bits & ONE_BIT_MASK;
-    if type_id == 4 { input.advance_by(6); }
-    else { input.advance_by(7); }
+    if type_id == 4 { input.advance_by(6)?; }
+    else { input.advance_by(7)?; }
     Ok((version as u8, type_id as u8, type_length_id as u8))
 }
 
-fn parse_packet_data(input: &mut BitString, type_id: u8, length_type_id: u8) -> Result<PacketData, char> {
+fn parse_packet_data(input: &mut BitString, type_id: u8, length_type_id: u8) -> Result<PacketData, ParsePacketError> {
     if type_id == 4 {
         return parse_literal_data(input);
     }
@@ -140,17 +211,16 @@ fn parse_packet_data(input: &mut BitString, type_id: u8, length_type_id: u8) ->
     parse_operator_data_mode1(input, type_id)
 }
 
-fn parse_literal_data(input: &mut BitString) -> Result<PacketData, char> {
+fn parse_literal_data(input: &mut BitString) -> Result<PacketData, ParsePacketError> {
     let mut still_ongoing = true;
     let mut counter = 0;
     let mut data: u64 = 0;
 
     while still_ongoing {
-        assert!(counter != 16, "more than 16 hex digits appeared in some packet data \
-            (17th digit at {{ {:?}, offset: {} }}", input.str, input.offset);
+        if counter == 16 { return Err(input.error(ParsePacketErrorKind::LiteralTooLong)); }
 
         let bits = input.peek_bits_with_u16(5)?;
-        input.advance_by(5);
+        input.advance_by(5)?;
         data <<= 4;
         data |= (bits & 0xF) as u64;
 
@@ -160,21 +230,23 @@ fn parse_literal_data(input: &mut BitString) -> Result<PacketData, char> {
     Ok(PacketData::Literal { data })
 }
 
-fn parse_operator_data_mode0(input: &mut BitString, type_id: u8) -> Result<PacketData, char> {
+// type ids have 3 bits and 4 is the literal type id, so the conversion to an `Operation` can't fail
+fn parse_operator_data_mode0(input: &mut BitString, type_id: u8) -> Result<PacketData, ParsePacketError> {
     let num_of_bits = input.peek_bits_with_u16(15)?;
-    input.advance_by(15);
+    input.advance_by(15)?;
     let mut target = *input;
-    target.advance_by(num_of_bits as usize);
+    target.advance_by(num_of_bits as usize)?;
 
     let mut subpackets = Vec::new();
     while !input.cursor_eq(&target) {
+        if input.pos() > target.pos() { return Err(input.error(ParsePacketErrorKind::UnexpectedEnd)); }
         subpackets.push(input.try_into()?);
     }
     Ok(PacketData::Operator { operation: type_id.try_into().unwrap(), subpackets })
 }
-fn parse_operator_data_mode1(input: &mut BitString, type_id: u8) -> Result<PacketData, char> {
+fn parse_operator_data_mode1(input: &mut BitString, type_id: u8) -> Result<PacketData, ParsePacketError> {
     let num_of_packets = input.peek_bits_with_u16(11)?;
-    input.advance_by(11);
+    input.advance_by(11)?;
 
     let mut subpackets = Vec::with_capacity(num_of_packets as usize);
     for _ in 0..num_of_packets {
@@ -184,20 +256,35 @@ fn parse_operator_data_mode1(input: &mut BitString, type_id: u8) -> Result<Packe
 }
 
 
-pub fn day16_main(file_data: &str) -> (u16, u64) {
-    let mut bit_string = BitString {
-        str: file_data,
-        offset: 0,
-        version_number_sum: 0
-    };
-    let packet: Packet = (&mut bit_string).try_into().unwrap_or_else(|c| {
-        panic!("invalid hex digit '{}' found in packet", c);
-    });
-    let packet_value = packet.evaluate();
-    println!("[Part 1] The sum of all packet version numbers is {}.", bit_string.version_number_sum);
+pub struct Day16;
+impl Solution for Day16 {
+    type Input = Packet;
+    type Answer1 = u16;
+    type Answer2 = u64;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        let mut bit_string = BitString::new(file_data.trim());
+        let indent = file_data.len() - file_data.trim_start().len();
+        (&mut bit_string).try_into().map_err(|e: ParsePacketError| PuzzleError::at(1, indent + e.pos + 1, e))
+    }
+
+    fn part1(packet: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(packet.version_sum())
+    }
+
+    fn part2(packet: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        Ok(packet.evaluate())
+    }
+}
+
+pub fn day16_main(file_data: &str) -> Result<(u16, u64), PuzzleError> {
+    let packet = Day16::parse(file_data)?;
+    let version_number_sum = Day16::part1(&packet)?;
+    let packet_value = Day16::part2(&packet)?;
+    println!("[Part 1] The sum of all packet version numbers is {}.", version_number_sum);
     println!("[Part 2] The packet evaluates to {}.", packet_value);
 
-    (bit_string.version_number_sum, packet_value)
+    Ok((version_number_sum, packet_value))
 }
 
 
@@ -207,25 +294,25 @@ mod tests {
 
     #[test]
     fn version_sum_test() {
-        assert_eq!(day16_main("D2FE28").0, 6);
-        assert_eq!(day16_main("38006F45291200").0, 1+6+2);
-        assert_eq!(day16_main("EE00D40C823060").0, 7+2+4+1);
-        assert_eq!(day16_main("8A004A801A8002F478").0, 16);
-        assert_eq!(day16_main("620080001611562C8802118E34").0, 12);
-        assert_eq!(day16_main("C0015000016115A2E0802F182340").0, 23);
-        assert_eq!(day16_main("A0016C880162017C3686B18A3D4780").0, 31);
+        assert_eq!(day16_main("D2FE28").unwrap().0, 6);
+        assert_eq!(day16_main("38006F45291200").unwrap().0, 1+6+2);
+        assert_eq!(day16_main("EE00D40C823060").unwrap().0, 7+2+4+1);
+        assert_eq!(day16_main("8A004A801A8002F478").unwrap().0, 16);
+        assert_eq!(day16_main("620080001611562C8802118E34").unwrap().0, 12);
+        assert_eq!(day16_main("C0015000016115A2E0802F182340").unwrap().0, 23);
+        assert_eq!(day16_main("A0016C880162017C3686B18A3D4780").unwrap().0, 31);
     }
 
     #[test]
     fn packet_value_test() {
-        assert_eq!(day16_main("C200B40A82").1, 3);
-        assert_eq!(day16_main("04005AC33890").1, 54);
-        assert_eq!(day16_main("880086C3E88112").1, 7);
-        assert_eq!(day16_main("CE00C43D881120").1, 9);
-        assert_eq!(day16_main("D8005AC2A8F0").1, 1);
-        assert_eq!(day16_main("F600BC2D8F").1, 0);
-        assert_eq!(day16_main("9C005AC2F8F0").1, 0);
-        assert_eq!(day16_main("9C0141080250320F1802104A08").1, 1);
+        assert_eq!(day16_main("C200B40A82").unwrap().1, 3);
+        assert_eq!(day16_main("04005AC33890").unwrap().1, 54);
+        assert_eq!(day16_main("880086C3E88112").unwrap().1, 7);
+        assert_eq!(day16_main("CE00C43D881120").unwrap().1, 9);
+        assert_eq!(day16_main("D8005AC2A8F0").unwrap().1, 1);
+        assert_eq!(day16_main("F600BC2D8F").unwrap().1, 0);
+        assert_eq!(day16_main("9C005AC2F8F0").unwrap().1, 0);
+        assert_eq!(day16_main("9C0141080250320F1802104A08").unwrap().1, 1);
     }
 
 }
\ No newline at end of file
@@ -1,55 +1,99 @@
 use std::cmp;
+use std::fmt;
+use std::error::Error;
+use std::num::ParseIntError;
+use crate::{Extra, Extras, Solution, PuzzleError, column_of};
+
+type Bounds = (i16, i16);
+pub type Velocity = (i16, i16);
+
+/// The initial velocity which makes the probe reach the highest point, displayed as that height.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct OptimalLaunch {
+    pub velocity: Velocity,
+    pub max_height: i16,
+}
+impl fmt::Display for OptimalLaunch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.max_height)
+    }
+}
 
 enum Dimension {
     X, Y
 }
 
 #[derive(Clone, Copy)]
-struct TargetArea {
-    x: (i16, i16),
-    y: (i16, i16),
+pub struct TargetArea {
+    x: Bounds,
+    y: Bounds,
 }
 
-fn parse_bounds(str: &str) -> Option<(Dimension, (i16, i16))> {
-    let mut tokens = str.trim().split(&['=', '.'][..]).filter(|&s| s != "");
+#[derive(Debug)]
+pub enum ParseTargetAreaError {
+    InvalidFormat, InvalidDimension, InvalidBound(ParseIntError), RepeatedDimension
+}
+impl fmt::Display for ParseTargetAreaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTargetAreaError::InvalidFormat => write!(f, "expected \"target area: x=<min>..<max>, y=<min>..<max>\""),
+            ParseTargetAreaError::InvalidDimension => write!(f, "bounds must be along the x or y axis"),
+            ParseTargetAreaError::InvalidBound(_) => write!(f, "bounds must be i16's"),
+            ParseTargetAreaError::RepeatedDimension => write!(f, "both bounds are along the same axis"),
+        }
+    }
+}
+impl Error for ParseTargetAreaError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseTargetAreaError::InvalidBound(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// on failure, returns the token that caused the error, which is a subslice of `str`
+fn parse_bounds(str: &str) -> Result<(Dimension, Bounds), (&str, ParseTargetAreaError)> {
+    let mut tokens = str.trim().split(&['=', '.'][..]).filter(|&s| !s.is_empty());
 
     let dim = match tokens.next() {
         Some("x") => Dimension::X,
         Some("y") => Dimension::Y,
-        _ => return None,
+        _ => return Err((str, ParseTargetAreaError::InvalidDimension)),
     };
 
     if let (Some(bound1), Some(bound2), None) = (tokens.next(), tokens.next(), tokens.next())  {
-        let bound1 = bound1.parse().ok()?;
-        let bound2 = bound2.parse().ok()?;
-        if bound2 > bound1 { return Some((dim, (bound1, bound2))) }
-        return Some((dim, (bound2, bound1)));
+        let bound1 = bound1.parse().map_err(|e| (bound1, ParseTargetAreaError::InvalidBound(e)))?;
+        let bound2 = bound2.parse().map_err(|e| (bound2, ParseTargetAreaError::InvalidBound(e)))?;
+        if bound2 > bound1 { return Ok((dim, (bound1, bound2))) }
+        return Ok((dim, (bound2, bound1)));
     }
 
-    None
+    Err((str, ParseTargetAreaError::InvalidFormat))
 }
-fn parse_target_area(str: &str) -> Option<TargetArea> {
-    let mut tokens = str.trim().split(&[' ', ','][..]).filter(|&s| s != "");
+fn parse_target_area(str: &str) -> Result<TargetArea, PuzzleError> {
+    let line = str.trim_end();
+    let mut tokens = line.split(&[' ', ','][..]).filter(|&s| !s.is_empty());
     if (tokens.next(), tokens.next()) != (Some("target"), Some("area:")) {
-        return None;
+        return Err(PuzzleError::at_line(1, ParseTargetAreaError::InvalidFormat));
     }
 
     if let (Some(token1), Some(token2), None) = (tokens.next(), tokens.next(), tokens.next()) {
-        if let (Some((dim1, bounds1)), Some((dim2, bounds2))) =
-            (parse_bounds(token1), parse_bounds(token2)) {
-            return match (dim1, dim2) {
-                (Dimension::X, Dimension::Y) => Some(TargetArea { x: bounds1, y: bounds2 }),
-                (Dimension::Y, Dimension::X) => Some(TargetArea { x: bounds2, y: bounds1 }),
-                _ => None,
-            }
+        let located = |(token, e)| PuzzleError::at(1, column_of(line, token), e);
+        let (dim1, bounds1) = parse_bounds(token1).map_err(located)?;
+        let (dim2, bounds2) = parse_bounds(token2).map_err(located)?;
+        return match (dim1, dim2) {
+            (Dimension::X, Dimension::Y) => Ok(TargetArea { x: bounds1, y: bounds2 }),
+            (Dimension::Y, Dimension::X) => Ok(TargetArea { x: bounds2, y: bounds1 }),
+            _ => Err(PuzzleError::at(1, column_of(line, token2), ParseTargetAreaError::RepeatedDimension)),
         }
     }
 
-    None
+    Err(PuzzleError::at_line(1, ParseTargetAreaError::InvalidFormat))
 }
 
 
-fn evaluate_trajectory(target: TargetArea, mut velocity: (i16, i16)) -> Option<i16> {
+fn evaluate_trajectory(target: TargetArea, mut velocity: Velocity) -> Option<i16> {
     debug_assert!(target.x.0 >= 0 && target.x.1 >= 0,
         "evaluate_trajectory cannot handle target areas on the left of the y axis");
     let mut pos = (0, 0);
@@ -70,7 +114,8 @@ fn evaluate_trajectory(target: TargetArea, mut velocity: (i16, i16)) -> Option<i
         velocity.1 -=1;
     }
 }
-fn find_optimal_pair_and_count_possibilities_interbal(target: TargetArea) -> (((i16, i16), i16), u16) {
+// the optimal pair is `None` if no initial velocity lands the probe in the target area
+pub fn find_optimal_pair_and_count_possibilities_interbal(target: TargetArea) -> (Option<(Velocity, i16)>, u16) {
     debug_assert!(target.x.0 >= 0 && target.x.1 >= 0,
         "find_optimal_pair_and_count_possibilities_interbal cannot handle target areas on the left of the y axis");
 
@@ -101,9 +146,9 @@ fn find_optimal_pair_and_count_possibilities_interbal(target: TargetArea) -> (((
         }
     }
 
-    (ideal_case.unwrap(), count)
+    (ideal_case, count)
 }
-fn find_optimal_pair_and_count_possibilities(target: TargetArea) -> (((i16, i16), i16), u16) {
+pub fn find_optimal_pair_and_count_possibilities(target: TargetArea) -> (Option<(Velocity, i16)>, u16) {
     // reduce all cases to the case where the target area is on or to the right of the y axis
     match (target.x.0 < 0, target.x.1 < 0) {
         (true, true) => {
@@ -111,7 +156,7 @@ fn find_optimal_pair_and_count_possibilities(target: TargetArea) -> (((i16, i16)
                 x: (target.x.1.abs(), target.x.0.abs()),
                 y: target.y
             });
-            result.0.0.0 *= -1;
+            if let Some(ref mut ideal_case) = result.0 { ideal_case.0.0 *= -1; }
             result
         },
         (true, false) => {
@@ -120,7 +165,7 @@ fn find_optimal_pair_and_count_possibilities(target: TargetArea) -> (((i16, i16)
                     x: (1, -target.x.0),
                     y: target.y
                 });
-            left_ideal_case.0.0 *= -1;
+            if let Some(ref mut ideal_case) = left_ideal_case { ideal_case.0.0 *= -1; }
             let (rigth_ideal_case, right_count) =
                 find_optimal_pair_and_count_possibilities_interbal(TargetArea {
                     x: (0, target.x.1),
@@ -128,9 +173,9 @@ fn find_optimal_pair_and_count_possibilities(target: TargetArea) -> (((i16, i16)
                 });
 
             let total_count = left_count + right_count;
-            let ideal_case = {
-                if left_ideal_case.1 > rigth_ideal_case.1 { left_ideal_case }
-                else { rigth_ideal_case }
+            let ideal_case = match (left_ideal_case, rigth_ideal_case) {
+                (Some(left), Some(right)) => Some(if left.1 > right.1 { left } else { right }),
+                (left, right) => left.or(right),
             };
             (ideal_case, total_count)
         },
@@ -139,16 +184,46 @@ fn find_optimal_pair_and_count_possibilities(target: TargetArea) -> (((i16, i16)
 }
 
 
-pub fn day17_main(file_data: &str) -> (((i16, i16), i16), u16) {
-    let target = parse_target_area(file_data)
-        .expect("Invalid input format!");
+pub struct Day17;
+impl Solution for Day17 {
+    type Input = TargetArea;
+    type Answer1 = OptimalLaunch;
+    type Answer2 = u16;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        parse_target_area(file_data)
+    }
+
+    fn part1(target: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        find_optimal_pair_and_count_possibilities(*target).0
+            .map(|(velocity, max_height)| OptimalLaunch { velocity, max_height })
+            .ok_or_else(target_unreachable)
+    }
+
+    fn part2(target: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        Ok(find_optimal_pair_and_count_possibilities(*target).1)
+    }
+
+    fn part1_extras(launch: &Self::Answer1) -> Extras {
+        let velocity = [launch.velocity.0, launch.velocity.1].map(|v| Extra::Int(v.into()));
+        vec![("velocity", Extra::List(velocity.to_vec()))]
+    }
+}
+
+fn target_unreachable() -> PuzzleError {
+    PuzzleError::no_solution("no initial velocity lands the probe in the target area")
+}
+
+pub fn day17_main(file_data: &str) -> Result<((Velocity, i16), u16), PuzzleError> {
+    let target = Day17::parse(file_data)?;
     let (result, count) = find_optimal_pair_and_count_possibilities(target);
+    let result = result.ok_or_else(target_unreachable)?;
     println!("[Part 1] The initial velocity ({}, {}) gives a maximum height of {}.",
         result.0.0, result.0.1, result.1);
     println!("[Part 2] There are {} intiial velocity settings that causes the probe to land in the target.",
         count);
 
-    (result, count)
+    Ok((result, count))
 }
 
 
@@ -159,9 +234,13 @@ mod tests {
     #[test]
     fn it_works() {
         let test_data = "target area: x=20..30, y=-10..-5";
-        let (part1_ans, part2_ans) = day17_main(test_data);
+        let (part1_ans, part2_ans) = day17_main(test_data).unwrap();
         assert_eq!(part1_ans.1, 45);
         assert_eq!(part2_ans, 112);
+
+        let target = Day17::parse(test_data).unwrap();
+        let extras = Day17::part1_extras(&Day17::part1(&target).unwrap());
+        assert_eq!(extras, vec![("velocity", Extra::List(vec![Extra::Int(6), Extra::Int(9)]))]);
     }
 
 }
\ No newline at end of file
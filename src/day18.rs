@@ -2,8 +2,10 @@ use std::str;
 use std::ops::Add;
 use std::iter::Sum;
 use std::error::Error;
+use std::io::BufRead;
 use std::num::ParseIntError;
 use std::fmt::{self, Display, Formatter};
+use crate::{read_lines, Solution, PuzzleError};
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 enum SnailfishToken {
@@ -53,6 +55,21 @@ impl<'a> SnailfishTokenStream<'a> {
     }
 }
 
+// the kind of token the parser expects next
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Expected {
+    Number, ClosingBrace, Comma
+}
+impl SnailfishToken {
+    fn matches_expectation(self, expected: Expected) -> bool {
+        match expected {
+            Expected::Comma => self == SnailfishToken::Comma,
+            Expected::ClosingBrace => self == SnailfishToken::ClosingBrace,
+            Expected::Number => matches!(self, SnailfishToken::Literal(_) | SnailfishToken::OpeningBrace),
+        }
+    }
+}
+
 #[derive(Debug)]
 enum ParseSnailfishNumberErrorKind {
     ParseRegularError(ParseIntError), UnresolvedTrailingChars, ExpectedComma, ExpectedClosingBrace, ExpectedNumber
@@ -73,9 +90,18 @@ impl From<ParseIntError> for ParseSnailfishNumberErrorKind {
         ParseSnailfishNumberErrorKind::ParseRegularError(e)
     }
 }
+impl From<Expected> for ParseSnailfishNumberErrorKind {
+    fn from(e: Expected) -> Self {
+        match e {
+            Expected::Number => ParseSnailfishNumberErrorKind::ExpectedNumber,
+            Expected::Comma => ParseSnailfishNumberErrorKind::ExpectedComma,
+            Expected::ClosingBrace => ParseSnailfishNumberErrorKind::ExpectedClosingBrace,
+        }
+    }
+}
 
 #[derive(Debug)]
-struct ParseSnailfishNumberError {
+pub struct ParseSnailfishNumberError {
     pos: usize,
     kind: ParseSnailfishNumberErrorKind,
 }
@@ -94,7 +120,7 @@ impl Error for ParseSnailfishNumberError {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-enum SnailfishNumber {
+pub enum SnailfishNumber {
     Zero,
     Regular(u8),
     Pair {
@@ -105,6 +131,14 @@ enum SnailfishNumber {
 impl SnailfishNumber {
     const ZERO: SnailfishNumber = SnailfishNumber::Zero;
 
+    // the number of pairs that the most deeply nested number is contained in
+    fn depth(&self) -> u8 {
+        match self {
+            SnailfishNumber::Pair { left, right } => 1 + left.depth().max(right.depth()),
+            _ => 0,
+        }
+    }
+
     fn get_magnitude(&self) -> u16 {
         match self {
             SnailfishNumber::Regular(x) => *x as u16,
@@ -234,29 +268,6 @@ impl Display for SnailfishNumber {
 impl str::FromStr for SnailfishNumber {
     type Err = ParseSnailfishNumberError;
     fn from_str(mut s: &str) -> Result<Self, Self::Err> {
-        #[derive(PartialEq, Eq, Clone, Copy)]
-        enum Expected {
-            Number, ClosingBrace, Comma
-        }
-        impl From<Expected> for ParseSnailfishNumberErrorKind {
-            fn from(e: Expected) -> Self {
-                match e {
-                    Expected::Number => ParseSnailfishNumberErrorKind::ExpectedNumber,
-                    Expected::Comma => ParseSnailfishNumberErrorKind::ExpectedComma,
-                    Expected::ClosingBrace => ParseSnailfishNumberErrorKind::ExpectedClosingBrace,
-                }
-            }
-        }
-        impl SnailfishToken {
-            fn matches_expectation(self, expected: Expected) -> bool {
-                match expected {
-                    Expected::Comma => self == SnailfishToken::Comma,
-                    Expected::ClosingBrace => self == SnailfishToken::ClosingBrace,
-                    Expected::Number => matches!(self, SnailfishToken::Literal(_) | SnailfishToken::OpeningBrace),
-                }
-            }
-        }
-
         s = s.trim();
         let mut stream = SnailfishTokenStream::new(s);
         let mut open_pairs = Vec::new();
@@ -276,7 +287,7 @@ impl str::FromStr for SnailfishNumber {
 
                 SnailfishToken::Literal(literal) => {
                     let num = SnailfishNumber::Regular(literal);
-                    let current_pair = open_pairs.iter_mut().rev().next();
+                    let current_pair = open_pairs.iter_mut().next_back();
 
                     if let Some(current_pair) = current_pair {
                         if current_pair.0.is_none() {
@@ -305,7 +316,7 @@ impl str::FromStr for SnailfishNumber {
                         left: resolved_pair.0.unwrap(),
                         right: resolved_pair.1.unwrap(),
                     };
-                    let next_pair = open_pairs.iter_mut().rev().next();
+                    let next_pair = open_pairs.iter_mut().next_back();
 
                     if let Some(next_pair) = next_pair {
                         if next_pair.0.is_none() {
@@ -356,36 +367,68 @@ impl Sum<Self> for SnailfishNumber {
 
 
 
-pub fn day18_main(file_data: &str) -> (u16, u16) {
-    let nums = file_data.lines().enumerate()
-        .map(|(i, num)| num.parse::<SnailfishNumber>().unwrap_or_else(|e| {
-            if let Some(src) = e.source() {
-                panic!("Error parsing SnailfishNumber on line {}: \n {}, caused by {}", i+1, e, src);
-            }
-            else { panic!("Error parsing SnailfishNumber on line {}: \n {}", i+1, e); }
-        }))
-        .collect::<Vec<_>>();
+fn parse_number(line_number: usize, line: &str) -> Result<SnailfishNumber, PuzzleError> {
+    let indent = line.len() - line.trim_start().len();
+    let num = line.parse::<SnailfishNumber>()
+        .map_err(|e| PuzzleError::at(line_number, indent + e.pos + 1, e))?;
+    // deeper numbers can't be reduced after an addition
+    if num.depth() > 4 {
+        return Err(PuzzleError::at_line(line_number, "snailfish numbers can be nested at most 4 pairs deep"));
+    }
+    Ok(num)
+}
+
+pub struct Day18;
+impl Solution for Day18 {
+    type Input = Vec<SnailfishNumber>;
+    type Answer1 = u16;
+    type Answer2 = u16;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        // shares the line handling of the reader, so trailing blank lines are skipped either way
+        Self::parse_reader(file_data.as_bytes())
+    }
+
+    fn parse_reader<R: BufRead>(reader: R) -> Result<Self::Input, PuzzleError> {
+        let nums = read_lines(reader)
+            .map(|line| {
+                let (line_number, line) = line?;
+                parse_number(line_number, &line)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if nums.is_empty() { return Err(PuzzleError::invalid("there are no numbers to sum")); }
+        Ok(nums)
+    }
+
+    fn part1(nums: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(nums.iter().cloned().sum::<SnailfishNumber>().get_magnitude())
+    }
+
+    fn part2(nums: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        nums.iter()
+            .enumerate()
+            .flat_map(|(i, num)| {
+                nums.iter().skip(i+1).cloned().flat_map(|other| {
+                    [(num.clone() + other.clone()).get_magnitude(), (other + num.clone()).get_magnitude()]
+                })
+            })
+            .max()
+            .ok_or_else(|| PuzzleError::no_solution("at least 2 numbers are needed to add a pair"))
+    }
+}
+
+pub fn day18_main(file_data: &str) -> Result<(u16, u16), PuzzleError> {
+    let nums = Day18::parse(file_data)?;
 
     // Part 1
-    let result = nums.iter().cloned().sum::<SnailfishNumber>().get_magnitude();
+    let result = Day18::part1(&nums)?;
     println!("[Part 1] The magnitude of the sum is {}.", result);
 
     // Part 2
-    let largest_magnitude = nums.iter()
-        .enumerate()
-        .map(|(i, num)| {
-            nums.iter().cloned().skip(i+1).map(|other| {
-                [(num.clone() + other.clone()).get_magnitude(), (other + num.clone()).get_magnitude()]
-            })
-            .flatten()
-        })
-        .flatten()
-        .max()
-        .expect("there are no numbers to sum");
-
+    let largest_magnitude = Day18::part2(&nums)?;
     println!("[Part 2] The possible largest magnitude is {}.", largest_magnitude);
 
-    (result, largest_magnitude)
+    Ok((result, largest_magnitude))
 }
 
 
@@ -512,8 +555,13 @@ mod tests {
         let expected = "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]".parse().unwrap();
         assert_eq!(sum, expected);
 
-        let (magnitude, largest_pair_magnitude) = day18_main(test_data);
+        let (magnitude, largest_pair_magnitude) = day18_main(test_data).unwrap();
         assert_eq!(magnitude, 4140);
         assert_eq!(largest_pair_magnitude, 3993);
+
+        // trailing blank lines are skipped whether the input is a string or a reader
+        let padded = format!("{}\n\n", test_data);
+        assert_eq!(Day18::parse(&padded).unwrap(), Day18::parse_reader(padded.as_bytes()).unwrap());
+        assert_eq!(Day18::part1(&Day18::parse(&padded).unwrap()).unwrap(), 4140);
     }
 }
\ No newline at end of file
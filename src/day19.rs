@@ -7,9 +7,10 @@ use std::str::FromStr;
 use std::num::ParseIntError;
 use std::collections::HashSet;
 use std::fmt::{self, Display, Formatter};
+use crate::{Solution, PuzzleError};
 
 #[derive(Debug)]
-enum ParsePositionError {
+pub enum ParsePositionError {
     InvalidDimension, ParseCoordinateError(ParseIntError)
 }
 impl From<ParseIntError> for ParsePositionError {
@@ -37,6 +38,7 @@ impl Error for ParsePositionError {
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 struct Position(i32, i32, i32);
+type Rotation = for<'r> fn(&'r mut Position);
 impl FromStr for Position {
     type Err = ParsePositionError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -94,8 +96,8 @@ impl Position {
     }
 
     // return iterator of operations that cycle through all possible orientations
-    fn get_orientation_walker() -> iter::Take<iter::Cycle<std::slice::Iter<'static, for<'r> fn(&'r mut Position)>>> {
-        static OPS: [fn(&mut Position); 8] =
+    fn get_orientation_walker() -> iter::Take<iter::Cycle<std::slice::Iter<'static, Rotation>>> {
+        static OPS: [Rotation; 8] =
             [Position::roll, Position::turn, Position::turn, Position::turn,
                 Position::roll, Position::turn_rev, Position::turn_rev, Position::turn_rev];
 
@@ -113,29 +115,33 @@ impl Position {
 
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Scanner {
+pub struct Scanner {
     scanned_beacons: Vec<Position>,
     fingerprint: HashSet<i32>,
     dedup_count: usize
 }
 impl FromStr for Scanner {
-    type Err = (ParsePositionError, usize);
+    type Err = PuzzleError;
 
+    // errors are located relative to the first line of `s`, which is the scanner's header
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.lines().next().unwrap_or("").trim().starts_with("--- scanner") {
+            return Err(PuzzleError::at_line(1, "expected a \"--- scanner <n> ---\" header"));
+        }
         let scanned_beacons = s.lines().skip(1)
             .enumerate()
             .map(|(i, s)| {
-                s.parse::<Position>().map_err(|e| (e, i+1))
+                s.parse::<Position>().map_err(|e| PuzzleError::at_line(i+2, e))
             })
             .collect::<Result<Vec<Position>, Self::Err>>()?;
+        if scanned_beacons.is_empty() { return Err(PuzzleError::at_line(1, "the scanner has no beacons")); }
 
         let fingerprint = scanned_beacons.iter().enumerate()
-            .map(|(i, pos)| {
+            .flat_map(|(i, pos)| {
                 scanned_beacons.iter().skip(i+1).map(|other| {
                     pos.distance_squared(other)
                 })
             })
-            .flatten()
             .collect::<HashSet<i32>>();
 
         let dedup_count = (scanned_beacons.len()*(scanned_beacons.len()-1))/2 - fingerprint.len();
@@ -146,7 +152,7 @@ impl FromStr for Scanner {
 }
 impl Scanner {
     // rotate the reference frame
-    fn rotate(&mut self, rotation: for<'r> fn(&'r mut Position)) {
+    fn rotate(&mut self, rotation: Rotation) {
         for pos in self.scanned_beacons.iter_mut() {
             rotation(pos);
         }
@@ -209,25 +215,10 @@ impl Scanner {
 
 
 
-pub fn day19_main(file_data: &str) -> (usize, i32) {
-    let block_delimiter = file_data.chars()
-        .nth(file_data.lines().next().unwrap().len());
-    let block_delimiter = match block_delimiter {
-        Some('\r') => "\r\n\r\n",
-        Some('\n') => "\n\n",
-        _ => unreachable!()
-    };
-
-    let mut scanners = file_data.split(block_delimiter)
-        .enumerate()
-        .map(|(i, s)| {
-            s.parse::<Scanner>().map_err(|e| (e, i))
-        })
-        .collect::<Result<Vec<Scanner>, _>>()
-        .unwrap_or_else(|((e, beacon_number), scanner_number)| {
-            panic!("unable to parse scanner {}, caused by ParsePositionError({}) when parsing beacon {}",
-                scanner_number, e, beacon_number);
-        });
+// Aligns all scanners, returning the set of all beacons and the position of each scanner, relative to
+// one of the scanners.
+fn locate_scanners(scanners: &[Scanner]) -> Result<(HashSet<Position>, Vec<Position>), PuzzleError> {
+    let mut scanners = scanners.to_vec();
 
     // The first scanner in `verified_scanners` is the reference scanner. All positions in `all_beacons` are relative
     // to this scanner, and all verified scanners are rotated to match its orientation as well.
@@ -239,6 +230,10 @@ pub fn day19_main(file_data: &str) -> (usize, i32) {
     let mut i = 0;
     let mut scanners_to_move = Vec::new();
     while !scanners.is_empty() {
+        if i == verified_scanners.len() {
+            let msg = format!("{} scanners do not overlap with any of the aligned scanners", scanners.len());
+            return Err(PuzzleError::no_solution(msg));
+        }
         for (scanner_index, scanner) in scanners.iter_mut().enumerate() {
             let result = scanner.try_align_with(&verified_scanners[i].1);
             if let Some(offset) = result {
@@ -263,21 +258,64 @@ pub fn day19_main(file_data: &str) -> (usize, i32) {
         i += 1;
     }
 
-    let part1_ans = all_beacons.len();
-    println!("[Part 1] There are {} beacons in total.", part1_ans);
+    let scanner_positions = verified_scanners.into_iter().map(|(pos, _)| pos).collect();
+    Ok((all_beacons, scanner_positions))
+}
 
-    let part2_ans = verified_scanners.iter().enumerate()
-        .map(|(i, (pos, _))| {
-            verified_scanners.iter().skip(i+1).map(|(other, _)| {
+fn largest_manhattan_distance(positions: &[Position]) -> Result<i32, PuzzleError> {
+    positions.iter().enumerate()
+        .flat_map(|(i, pos)| {
+            positions.iter().skip(i+1).map(|other| {
                 pos.manhattan_distance(other)
             })
         })
-        .flatten()
         .max()
-        .unwrap();
+        .ok_or_else(|| PuzzleError::no_solution("at least 2 scanners are needed to measure a distance"))
+}
+
+pub struct Day19;
+impl Solution for Day19 {
+    type Input = Vec<Scanner>;
+    type Answer1 = usize;
+    type Answer2 = i32;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        let first_line = file_data.lines().next().ok_or_else(|| PuzzleError::invalid("the input is empty"))?;
+        let block_delimiter = match file_data.chars().nth(first_line.len()) {
+            Some('\r') => "\r\n\r\n",
+            _ => "\n\n",
+        };
+
+        let mut line_offset = 0;
+        file_data.split(block_delimiter)
+            .map(|s| {
+                let scanner = s.parse::<Scanner>().map_err(|e| e.offset_lines(line_offset));
+                line_offset += s.lines().count() + 1;
+                scanner
+            })
+            .collect()
+    }
+
+    fn part1(scanners: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(locate_scanners(scanners)?.0.len())
+    }
+
+    fn part2(scanners: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        largest_manhattan_distance(&locate_scanners(scanners)?.1)
+    }
+}
+
+pub fn day19_main(file_data: &str) -> Result<(usize, i32), PuzzleError> {
+    let scanners = Day19::parse(file_data)?;
+    let (all_beacons, scanner_positions) = locate_scanners(&scanners)?;
+
+    let part1_ans = all_beacons.len();
+    println!("[Part 1] There are {} beacons in total.", part1_ans);
+
+    let part2_ans = largest_manhattan_distance(&scanner_positions)?;
     println!("[Part 2] The largest manhattan distance between any two scanners is {}.", part2_ans);
 
-    (part1_ans, part2_ans)
+    Ok((part1_ans, part2_ans))
 }
 
 
@@ -505,6 +543,6 @@ pub mod tests {
             -652,-548,-490
             30,-46,-14";
 
-        assert_eq!(day19_main(test_data), (79, 3621));
+        assert_eq!(day19_main(test_data).unwrap(), (79, 3621));
     }
 }
\ No newline at end of file
@@ -1,67 +1,540 @@
-use std::process;
+use std::fmt;
+use std::iter::{self, Peekable};
+use std::error::Error;
+use std::io::BufRead;
+use std::num::ParseIntError;
+use crate::{read_lines, Solution, PuzzleError};
 
-enum Command {
-    Forward(i8),
-    Down(i8)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Command {
+    Forward(u32),
+    Back(u32),
+    Down(u32),
+    Up(u32),
+    Wait,
+    Repeat(u32, Vec<Command>),
+}
+// commands are written back in a form that can be parsed again, with repeats on a single line
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Forward(units) => write!(f, "forward {}", units),
+            Command::Back(units) => write!(f, "back {}", units),
+            Command::Down(units) => write!(f, "down {}", units),
+            Command::Up(units) => write!(f, "up {}", units),
+            Command::Wait => write!(f, "wait"),
+            Command::Repeat(times, body) => {
+                write!(f, "repeat {} {{", times)?;
+                for command in body {
+                    write!(f, " {}", command)?;
+                }
+                write!(f, " }}")
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseCommandError {
+    InvalidCommand(String), MissingUnits(String), InvalidUnits(ParseIntError), MissingOpeningBrace,
+    UnclosedRepeat, UnexpectedClosingBrace, NestedTooDeeply
+}
+impl fmt::Display for ParseCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseCommandError::InvalidCommand(s) => write!(f, "invalid command \"{}\"", s),
+            ParseCommandError::MissingUnits(s) => write!(f, "\"{}\" must be followed by a number on the same line", s),
+            ParseCommandError::InvalidUnits(_) => write!(f, "invalid units to move"),
+            ParseCommandError::MissingOpeningBrace => write!(f, "expected \"{{\" after \"repeat <N>\""),
+            ParseCommandError::UnclosedRepeat => write!(f, "this repeat is never closed with \"}}\""),
+            ParseCommandError::UnexpectedClosingBrace => write!(f, "\"}}\" does not close a repeat"),
+            ParseCommandError::NestedTooDeeply =>
+                write!(f, "repeats may only be nested {} deep", MAX_REPEAT_NESTING),
+        }
+    }
+}
+impl Error for ParseCommandError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseCommandError::InvalidUnits(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
-pub fn day2_main(file_data: &str) {
-    // parse lines to Command iterator
-    let commands = file_data.lines().enumerate().map(|(i, line)| {
-        let tokens: Vec<_> = line.split_whitespace().collect();
-        let line_number = i + 1;
+// a word, number or brace of the course, along with where it starts
+struct Token {
+    text: String,
+    line: usize,
+    col: usize,
+}
+impl Token {
+    fn error(&self, e: ParseCommandError) -> PuzzleError {
+        PuzzleError::at(self.line, self.col, e)
+    }
+}
 
-        // check number of tokens
-        if tokens.len() != 2 {
-            eprintln!("Parse Error (line {}): each line can only have 2 tokens", i+1);
-            process::exit(1);
+// splits a line at whitespace, where braces are tokens of their own
+fn tokenize(line_number: usize, line: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+    for (i, c) in line.char_indices().chain(iter::once((line.len(), ' '))) {
+        let is_brace = c == '{' || c == '}';
+        if !c.is_whitespace() && !is_brace {
+            word_start.get_or_insert(i);
+            continue;
+        }
+        if let Some(start) = word_start.take() {
+            tokens.push(Token { text: line[start..i].to_owned(), line: line_number, col: start+1 });
+        }
+        if is_brace {
+            tokens.push(Token { text: c.to_string(), line: line_number, col: i+1 });
         }
+    }
+    tokens
+}
 
-        // parse the units to move
-        let move_units = tokens[1].parse::<i8>().unwrap_or_else(|e| {
-            eprintln!("Parse Error (line {}): ParseIntError{{ {} }}", i+1, e);
-            process::exit(1);
-        });
-
-        // parse command
-        match tokens[0] {
-            "forward" => Command::Forward(move_units),
-            "down" => Command::Down(move_units),
-            "up" => Command::Down(-move_units),
-            _ => {
-                eprintln!("Parse Error (line {}): invalid command", i+1);
-                process::exit(1);
-            }
+fn tokenize_lines<I>(lines: I) -> impl Iterator<Item = Result<Token, PuzzleError>>
+where
+    I: Iterator<Item = Result<(usize, String), PuzzleError>>,
+{
+    lines.flat_map(|line| match line {
+        Ok((line_number, line)) => tokenize(line_number, &line).into_iter().map(Ok).collect(),
+        Err(e) => vec![Err(e)],
+    })
+}
+
+// bounds the recursion of `parse_commands`, so deeply nested input can't overflow the stack
+const MAX_REPEAT_NESTING: usize = 64;
+
+// parses commands until the end of the input, or until the brace closing `open_brace` if the commands are the body
+// of a repeat nested `depth` deep
+fn parse_commands<I>(tokens: &mut Peekable<I>, open_brace: Option<&Token>, depth: usize)
+    -> Result<Vec<Command>, PuzzleError>
+where
+    I: Iterator<Item = Result<Token, PuzzleError>>,
+{
+    let mut commands = Vec::new();
+    while let Some(token) = tokens.next() {
+        let token = token?;
+        let command = match token.text.as_str() {
+            "}" if open_brace.is_some() => return Ok(commands),
+            "}" => return Err(token.error(ParseCommandError::UnexpectedClosingBrace)),
+            "forward" => Command::Forward(parse_units(tokens, &token)?),
+            "back" => Command::Back(parse_units(tokens, &token)?),
+            "down" => Command::Down(parse_units(tokens, &token)?),
+            "up" => Command::Up(parse_units(tokens, &token)?),
+            "wait" => Command::Wait,
+            "repeat" => {
+                if depth == MAX_REPEAT_NESTING { return Err(token.error(ParseCommandError::NestedTooDeeply)); }
+                let times = parse_units(tokens, &token)?;
+                let open_brace = match tokens.next().transpose()? {
+                    Some(brace) if brace.text == "{" => brace,
+                    Some(other) => return Err(other.error(ParseCommandError::MissingOpeningBrace)),
+                    None => return Err(token.error(ParseCommandError::MissingOpeningBrace)),
+                };
+                Command::Repeat(times, parse_commands(tokens, Some(&open_brace), depth + 1)?)
+            },
+            s => return Err(token.error(ParseCommandError::InvalidCommand(s.to_owned()))),
+        };
+        commands.push(command);
+    }
+
+    match open_brace {
+        Some(brace) => Err(brace.error(ParseCommandError::UnclosedRepeat)),
+        None => Ok(commands),
+    }
+}
+
+// the number following `keyword`, which must be on the same line
+fn parse_units<I>(tokens: &mut Peekable<I>, keyword: &Token) -> Result<u32, PuzzleError>
+where
+    I: Iterator<Item = Result<Token, PuzzleError>>,
+{
+    match tokens.next_if(|token| !matches!(token, Ok(token) if token.line != keyword.line)) {
+        Some(Ok(token)) => token.text.parse::<u32>().map_err(|e| token.error(ParseCommandError::InvalidUnits(e))),
+        Some(Err(e)) => Err(e),
+        None => Err(keyword.error(ParseCommandError::MissingUnits(keyword.text.clone()))),
+    }
+}
+
+/// How the submarine moves: in part 1 `down` and `up` change the depth directly, while in part 2 they change the
+/// aim, which `forward` and `back` then follow.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Model {
+    Direct, Aim
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PositionOverflow;
+impl fmt::Display for PositionOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the position of the submarine overflows")
+    }
+}
+impl Error for PositionOverflow {}
+
+impl From<PositionOverflow> for PuzzleError {
+    fn from(e: PositionOverflow) -> Self {
+        PuzzleError::no_solution(e.to_string())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Submarine {
+    pub model: Model,
+    pub horizontal: i64,
+    pub depth: i64,
+    pub aim: i64,
+}
+impl Submarine {
+    pub fn new(model: Model) -> Self {
+        Submarine { model, horizontal: 0, depth: 0, aim: 0 }
+    }
+
+    /// Executes `command`, applying a repeat in closed form rather than iteration by iteration, so only the terms
+    /// of its overall effect must fit rather than every position along the way. The submarine is left unchanged by
+    /// a command which would overflow its position.
+    pub fn execute(&mut self, command: &Command) -> Result<(), PositionOverflow> {
+        *self = self.after(command).ok_or(PositionOverflow)?;
+        Ok(())
+    }
+
+    /// Executes each command in turn, stopping at the first which would overflow the position.
+    pub fn run(&mut self, commands: &[Command]) -> Result<(), PositionOverflow> {
+        commands.iter().try_for_each(|command| self.execute(command))
+    }
+
+    /// The state of the submarine after each command is executed, where repeats are expanded into the commands of
+    /// each iteration. The trace ends after the first command which overflows the position.
+    pub fn trace<'a>(mut self, commands: &'a [Command])
+        -> impl Iterator<Item = Result<(&'a Command, Submarine), PositionOverflow>> + 'a
+    {
+        let mut overflowed = false;
+        expand(commands).map_while(move |command| {
+            if overflowed { return None; }
+            let result = self.execute(command).map(|_| (command, self));
+            overflowed = result.is_err();
+            Some(result)
+        })
+    }
+
+    /// The horizontal position multiplied by the depth.
+    pub fn product(&self) -> Result<i64, PositionOverflow> {
+        self.horizontal.checked_mul(self.depth).ok_or(PositionOverflow)
+    }
+
+    // the state after a command, or `None` on overflow
+    fn after(&self, command: &Command) -> Option<Submarine> {
+        match command {
+            Command::Repeat(times, body) => self.repeat(*times, body),
+            _ => self.step(command),
         }
-    });
+    }
 
+    // the state after `times` iterations of `body`. Starting from the origin, an iteration moves forward by
+    // `horizontal` and changes the aim by `aim` and the depth by `depth`. From any other state, it moves forward and
+    // changes the aim by the same amounts, but also changes the depth by the aim at its start times the distance
+    // forward (which is always 0 in the direct model). Summing over the iterations, where the aim at the start of
+    // the kth is `aim + k*effect.aim`, gives the closed form.
+    fn repeat(&self, times: u32, body: &[Command]) -> Option<Submarine> {
+        if times == 0 { return Some(*self); }
+        let effect = body.iter().try_fold(Submarine::new(self.model), |state, command| state.after(command))?;
+        let n = i64::from(times);
+        let earlier_pairs = i64::try_from(u64::from(times) * u64::from(times - 1) / 2).ok()?;   // sum of k < n
+        let depth_per_iteration = self.aim.checked_mul(effect.horizontal)?.checked_add(effect.depth)?;
+        let depth_from_aim_changes = earlier_pairs.checked_mul(effect.aim)?.checked_mul(effect.horizontal)?;
+        Some(Submarine {
+            model: self.model,
+            horizontal: self.horizontal.checked_add(n.checked_mul(effect.horizontal)?)?,
+            depth: self.depth.checked_add(n.checked_mul(depth_per_iteration)?)?.checked_add(depth_from_aim_changes)?,
+            aim: self.aim.checked_add(n.checked_mul(effect.aim)?)?,
+        })
+    }
 
-    // Part 1
-    let mut result: [i32; 2] = [0, 0];
-    for command in commands.clone() {
+    // the state after a command which is not a repeat, or `None` on overflow
+    fn step(&self, command: &Command) -> Option<Submarine> {
+        let mut next = *self;
+        match (command, self.model) {
+            (Command::Forward(units), Model::Direct) => next.horizontal = self.horizontal.checked_add((*units).into())?,
+            (Command::Back(units), Model::Direct) => next.horizontal = self.horizontal.checked_sub((*units).into())?,
+            (Command::Down(units), Model::Direct) => next.depth = self.depth.checked_add((*units).into())?,
+            (Command::Up(units), Model::Direct) => next.depth = self.depth.checked_sub((*units).into())?,
+            (Command::Forward(units), Model::Aim) => {
+                next.horizontal = self.horizontal.checked_add((*units).into())?;
+                next.depth = self.depth.checked_add(self.aim.checked_mul((*units).into())?)?;
+            },
+            (Command::Back(units), Model::Aim) => {
+                next.horizontal = self.horizontal.checked_sub((*units).into())?;
+                next.depth = self.depth.checked_sub(self.aim.checked_mul((*units).into())?)?;
+            },
+            (Command::Down(units), Model::Aim) => next.aim = self.aim.checked_add((*units).into())?,
+            (Command::Up(units), Model::Aim) => next.aim = self.aim.checked_sub((*units).into())?,
+            (Command::Wait, _) | (Command::Repeat(..), _) => (),
+        }
+        Some(next)
+    }
+}
+impl fmt::Display for Submarine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "horizontal {}, depth {}", self.horizontal, self.depth)?;
+        if self.model == Model::Aim { write!(f, ", aim {}", self.aim)?; }
+        Ok(())
+    }
+}
+
+// the commands in the order they are executed, with repeats expanded into the commands of each iteration
+fn expand(commands: &[Command]) -> Box<dyn Iterator<Item = &Command> + '_> {
+    Box::new(commands.iter().flat_map(|command| -> Box<dyn Iterator<Item = &Command>> {
         match command {
-            Command::Forward(move_units) => result[0] += move_units as i32,
-            Command::Down(move_units) => result[1] += move_units as i32,
+            Command::Repeat(times, body) => Box::new((0..*times).flat_map(move |_| expand(body))),
+            _ => Box::new(iter::once(command)),
         }
+    }))
+}
+
+/// The area covered by a trajectory, as its smallest and largest horizontal positions and depths.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CourseBounds {
+    pub horizontal: (i64, i64),
+    pub depth: (i64, i64),
+}
+
+/// Every state of the submarine under one model, from the start and after each command.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Trajectory {
+    model: Model,
+    commands: Vec<String>,      // the command which led to each state but the first
+    states: Vec<Submarine>,
+}
+impl Trajectory {
+    pub fn new(commands: &[Command], model: Model) -> Result<Self, PositionOverflow> {
+        let start = Submarine::new(model);
+        let mut trajectory = Trajectory { model, commands: Vec::new(), states: vec![start] };
+        for step in start.trace(commands) {
+            let (command, state) = step?;
+            trajectory.commands.push(command.to_string());
+            trajectory.states.push(state);
+        }
+        Ok(trajectory)
     }
-    println!("Part 1:");
-    println!("The final position of the submarine is {:?}.", result);
-    println!("The answer is {}!", result[0] * result[1]);
 
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    pub fn states(&self) -> &[Submarine] {
+        &self.states
+    }
+
+    pub fn max_depth(&self) -> i64 {
+        self.states.iter().map(|state| state.depth).max().expect("a trajectory always has its start")
+    }
+
+    pub fn bounds(&self) -> CourseBounds {
+        let min_max = |f: fn(&Submarine) -> i64| {
+            let values = self.states.iter().map(f);
+            (values.clone().min().unwrap_or_default(), values.max().unwrap_or_default())
+        };
+        CourseBounds { horizontal: min_max(|state| state.horizontal), depth: min_max(|state| state.depth) }
+    }
+
+    /// The length of the path between each consecutive pair of positions, where a move along the aim is a straight
+    /// line rather than a horizontal and a vertical one.
+    pub fn total_distance(&self) -> f64 {
+        self.states.windows(2)
+            .map(|pair| {
+                let dx = (pair[1].horizontal - pair[0].horizontal) as f64;
+                let dy = (pair[1].depth - pair[0].depth) as f64;
+                dx.hypot(dy)
+            })
+            .sum()
+    }
+
+    /// One row per state with its step number, the command leading to it (empty for the start) and its position.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,command,horizontal,depth,aim\n");
+        let commands = iter::once("").chain(self.commands.iter().map(String::as_str));
+        for (i, (command, state)) in commands.zip(&self.states).enumerate() {
+            csv.push_str(&format!("{},{},{},{},{}\n", i, command, state.horizontal, state.depth, state.aim));
+        }
+        csv
+    }
+}
+
+const SVG_COLOURS: [&str; 4] = ["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e"];
+
+/// Draws each trajectory as a polyline in a single SVG image, with the depth increasing downwards.
+pub fn trajectories_to_svg(trajectories: &[Trajectory]) -> String {
+    let bounds = trajectories.iter().map(Trajectory::bounds)
+        .reduce(|a, b| CourseBounds {
+            horizontal: (a.horizontal.0.min(b.horizontal.0), a.horizontal.1.max(b.horizontal.1)),
+            depth: (a.depth.0.min(b.depth.0), a.depth.1.max(b.depth.1)),
+        })
+        .unwrap_or(CourseBounds { horizontal: (0, 0), depth: (0, 0) });
+    // the view is padded so lines along its edges are fully visible
+    let width = (bounds.horizontal.1 - bounds.horizontal.0).max(1);
+    let height = (bounds.depth.1 - bounds.depth.0).max(1);
+    let padding = width.max(height) / 50 + 1;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" preserveAspectRatio=\"none\">\n",
+        bounds.horizontal.0 - padding, bounds.depth.0 - padding, width + 2*padding, height + 2*padding);
+    for (trajectory, colour) in trajectories.iter().zip(SVG_COLOURS.iter().cycle()) {
+        let points = trajectory.states.iter()
+            .map(|state| format!("{},{}", state.horizontal, state.depth))
+            .collect::<Vec<_>>();
+        svg.push_str(&format!(
+            "  <polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" vector-effect=\"non-scaling-stroke\" \
+            points=\"{}\"><title>{:?}</title></polyline>\n",
+            colour, points.join(" "), trajectory.model));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn final_position(commands: &[Command], model: Model) -> Result<Submarine, PuzzleError> {
+    let mut submarine = Submarine::new(model);
+    submarine.run(commands)?;
+    Ok(submarine)
+}
+
+pub struct Day2;
+impl Solution for Day2 {
+    type Input = Vec<Command>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        // shares the line handling of the reader, so trailing blank lines are skipped either way
+        Self::parse_reader(file_data.as_bytes())
+    }
+
+    fn parse_reader<R: BufRead>(reader: R) -> Result<Self::Input, PuzzleError> {
+        parse_commands(&mut tokenize_lines(read_lines(reader)).peekable(), None, 0)
+    }
+
+    fn part1(commands: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(final_position(commands, Model::Direct)?.product()?)
+    }
+
+    fn part2(commands: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        Ok(final_position(commands, Model::Aim)?.product()?)
+    }
+}
+
+/// Prints the state of the submarine after every command under `model`, and returns its final state.
+pub fn day2_trace(file_data: &str, model: Model) -> Result<Submarine, PuzzleError> {
+    let commands = Day2::parse(file_data)?;
+    let mut submarine = Submarine::new(model);
+    for step in submarine.trace(&commands) {
+        let (command, state) = step?;
+        println!("{:<12} -> {}", command.to_string(), state);
+        submarine = state;
+    }
+    Ok(submarine)
+}
+
+pub fn day2_main(file_data: &str) -> Result<(i64, i64), PuzzleError> {
+    let commands = Day2::parse(file_data)?;
 
     // Part 1
-    let mut result: [i32; 2] = [0, 0];
-    let mut aim = 0;
-    for command in commands {
-        match command {
-            Command::Forward(move_units) => {
-                result[0] += move_units as i32;
-                result[1] += aim * (move_units as i32);
+    let result = final_position(&commands, Model::Direct)?;
+    println!("Part 1:");
+    println!("The final position of the submarine is {}.", result);
+    let part1_answer = result.product()?;
+    println!("The answer is {}!", part1_answer);
+
+
+    // Part 2
+    let result = final_position(&commands, Model::Aim)?;
+    println!("\nPart 2:");
+    println!("The final position of the submarine is {}.", result);
+    let part2_answer = result.product()?;
+    println!("The answer is {}!", part2_answer);
+
+    // Course statistics
+    for model in [Model::Direct, Model::Aim] {
+        let trajectory = Trajectory::new(&commands, model)?;
+        println!("\n{:?} model: the maximum depth is {} and the distance travelled is {:.1}.",
+            model, trajectory.max_depth(), trajectory.total_distance());
+    }
+
+    Ok((part1_answer, part2_answer))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let test_data =
+            "forward 5
+            down 5
+            forward 8
+            up 3
+            down 8
+            forward 2";
+        assert_eq!(day2_main(test_data).unwrap(), (150, 900));
+
+        let commands = Day2::parse(
+            "repeat 2 {
+                forward 3 wait
+                repeat 2 { down 1 }
             }
-            Command::Down(move_units) => aim += move_units as i32
+            back 1").unwrap();
+        assert_eq!(commands[0].to_string(), "repeat 2 { forward 3 wait repeat 2 { down 1 } }");
+        let trace = Submarine::new(Model::Aim).trace(&commands).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(trace.len(), 9);
+        assert_eq!(trace[5].1, Submarine { model: Model::Aim, horizontal: 6, depth: 6, aim: 2 });
+        assert_eq!(trace[8].1, Submarine { model: Model::Aim, horizontal: 5, depth: 2, aim: 4 });
+        assert_eq!(final_position(&commands, Model::Direct).unwrap().product(), Ok(20));
+
+        let trajectory = Trajectory::new(&Day2::parse(test_data).unwrap(), Model::Aim).unwrap();
+        assert_eq!(trajectory.states().len(), 7);
+        assert_eq!(trajectory.max_depth(), 60);
+        assert_eq!(trajectory.bounds(), CourseBounds { horizontal: (0, 15), depth: (0, 60) });
+        assert_eq!(trajectory.total_distance(), 5.0 + 8f64.hypot(40.0) + 2f64.hypot(20.0));
+        assert_eq!(trajectory.to_csv().lines().nth(4), Some("3,forward 8,13,40,5"));
+        let svg = trajectories_to_svg(&[trajectory]);
+        assert!(svg.contains("points=\"0,0 5,0 5,0 13,40 13,40 13,40 15,60\""));
+
+        let err = Day2::parse("forward 1\nrepeat 2 {\n  up x\n}").unwrap_err();
+        assert_eq!(err.location(), Some(crate::Location { line: 3, col: Some(6) }));
+        let err = Day2::parse("repeat 2 {\n  up 1").unwrap_err();
+        assert_eq!(err.location(), Some(crate::Location { line: 1, col: Some(10) }));
+        let err = Day2::parse("down\n3").unwrap_err();
+        assert_eq!(err.location(), Some(crate::Location { line: 1, col: Some(1) }));
+
+        // repeats are applied in closed form, and agree with executing each iteration
+        let commands = Day2::parse("repeat 4294967295 { repeat 4294967295 { forward 1 } }").unwrap();
+        assert!(Day2::part1(&commands).is_err());
+        let commands = Day2::parse("down 1 repeat 4294967295 { repeat 1000 { forward 1 } }").unwrap();
+        assert_eq!(Day2::part1(&commands).unwrap(), 4294967295000);
+        assert_eq!(final_position(&commands, Model::Aim).unwrap().depth, 4294967295000);
+        let commands = Day2::parse("down 3 repeat 5 { forward 2 up 1 repeat 3 { down 2 back 1 } } forward 4").unwrap();
+        for model in [Model::Direct, Model::Aim] {
+            let mut traced = Submarine::new(model);
+            for step in Submarine::new(model).trace(&commands) {
+                traced = step.unwrap().1;
+            }
+            assert_eq!(final_position(&commands, model).unwrap(), traced);
         }
+        let mut submarine = Submarine::new(Model::Aim);
+        submarine.run(&Day2::parse("down 3 forward 1").unwrap()).unwrap();
+        let overflowing = Day2::parse("repeat 4294967295 { repeat 4294967295 { forward 4294967295 } }").unwrap();
+        assert_eq!(submarine.execute(&overflowing[0]), Err(PositionOverflow));
+        assert_eq!(submarine, Submarine { model: Model::Aim, horizontal: 1, depth: 3, aim: 3 });
+
+        let nested = "repeat 1 { ".repeat(MAX_REPEAT_NESTING + 1) + &"}".repeat(MAX_REPEAT_NESTING + 1);
+        let err = Day2::parse(&nested).unwrap_err();
+        assert_eq!(err.location(), Some(crate::Location { line: 1, col: Some(11*MAX_REPEAT_NESTING + 1) }));
+        assert!(Day2::parse(&nested[11..nested.len() - 1]).is_ok());
+
+        let padded = format!("{}\n\n\n", test_data);
+        assert_eq!(Day2::parse(&padded).unwrap(), Day2::parse_reader(padded.as_bytes()).unwrap());
+        assert_eq!(Day2::parse(&padded).unwrap(), Day2::parse(test_data).unwrap());
     }
-    println!("\nPart 2:");
-    println!("The final position of the submarine is {:?}.", result);
-    println!("The answer is {}!", result[0] * result[1]);
-}
\ No newline at end of file
+}
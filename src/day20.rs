@@ -2,7 +2,8 @@ use std::mem;
 use std::str::FromStr;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
-use std::ops::{Index, IndexMut};
+use crate::grid::Grid;
+use crate::{Solution, PuzzleError};
 
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -27,24 +28,10 @@ impl Display for Pixel {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-struct Position(usize, usize);
-impl Index<Position> for Image {
-    type Output = Pixel;
-
-    fn index(&self, index: Position) -> &Self::Output {
-        &self.pixels[self.size.1 * index.0 + index.1]
-    }
-}
-impl IndexMut<Position> for Image {
-    fn index_mut(&mut self, index: Position) -> &mut Self::Output {
-        &mut self.pixels[self.size.1 * index.0 + index.1]
-    }
-}
 
 #[derive(Debug)]
-enum ParseImageError {
-    MissingAlgorithm, MissingImage, InvalidPixel(char), IncorrectAlgorithmSize, IncosistentRowLength, UnidentifiedTrailingData
+pub enum ParseImageError {
+    MissingAlgorithm, MissingImage, InvalidPixel(char), IncorrectAlgorithmSize, UnidentifiedTrailingData
 }
 impl Display for ParseImageError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -53,71 +40,55 @@ impl Display for ParseImageError {
             ParseImageError::MissingImage => write!(f, "missing image"),
             ParseImageError::InvalidPixel(c) => write!(f, "invalid pixel '{}' was found", c),
             ParseImageError::IncorrectAlgorithmSize => write!(f, "the algorithms must specify exactly 512 bits"),
-            ParseImageError::IncosistentRowLength => write!(f, "the length of the image rows are inconsistent"),
             ParseImageError::UnidentifiedTrailingData => write!(f, "unindentified trailing data"),
         }
     }
 }
 impl Error for ParseImageError {}
 
-#[derive(Debug)]
-struct Image {
-    pixels: Vec<Pixel>,
+#[derive(Debug, Clone)]
+pub struct Image {
+    pixels: Grid<Pixel>,
     buffer: Vec<Pixel>,
     padding_pixel: Pixel,
-    size: Position,
     algorithm: Vec<Pixel>,
 }
 impl FromStr for Image {
-    type Err = ParseImageError;
+    type Err = PuzzleError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let block_delimiter = s.chars().nth(s.lines().next().unwrap().chars().count());
+        let first_line = s.lines().next().ok_or_else(|| PuzzleError::invalid(ParseImageError::MissingAlgorithm))?;
+        let block_delimiter = s.chars().nth(first_line.chars().count());
         let block_delimiter = match block_delimiter {
             Some('\r') => "\r\n\r\n",
             Some('\n') => "\n\n",
-            _ => return Err(ParseImageError::MissingAlgorithm),
+            _ => return Err(PuzzleError::invalid(ParseImageError::MissingImage)),
         };
 
         let mut blocks = s.split(block_delimiter);
-        let algorithm = blocks.next().ok_or(ParseImageError::MissingAlgorithm)?;
-        let pixels = blocks.next().ok_or(ParseImageError::MissingImage)?;
+        let algorithm = blocks.next().ok_or_else(|| PuzzleError::invalid(ParseImageError::MissingAlgorithm))?;
+        let pixels = blocks.next().ok_or_else(|| PuzzleError::invalid(ParseImageError::MissingImage))?;
         if blocks.next().is_some() {
-            return Err(ParseImageError::UnidentifiedTrailingData);
+            return Err(PuzzleError::invalid(ParseImageError::UnidentifiedTrailingData));
         }
 
-        let algorithm = algorithm.trim().chars()
-            .map(|c| {
-                Pixel::from_char(c).ok_or(ParseImageError::InvalidPixel(c))
-            })
-            .collect::<Result<Vec<Pixel>, ParseImageError>>()?;
-        if algorithm.len() != 512 {
-            return Err(ParseImageError::IncorrectAlgorithmSize);
-        }
+        let parse_pixel = |c| Pixel::from_char(c).ok_or(ParseImageError::InvalidPixel(c));
 
-        let mut size = Position(0, 0);
-        if let Some(first_line) = pixels.lines().next() {
-            size.1 = first_line.trim().chars().count();
-        }
-        else {
-            return Err(ParseImageError::MissingImage);
+        // the algorithm is a single row of pixels
+        let indent = algorithm.len() - algorithm.trim_start().len();
+        let algorithm = algorithm.trim().chars().enumerate()
+            .map(|(j, c)| parse_pixel(c).map_err(|e| PuzzleError::at(1, indent+j+1, e)))
+            .collect::<Result<Vec<Pixel>, PuzzleError>>()?;
+        if algorithm.len() != 512 {
+            return Err(PuzzleError::at_line(1, ParseImageError::IncorrectAlgorithmSize));
         }
 
-        let pixels = pixels.trim().lines()
-            .flat_map(|s| {
-                s.trim().chars().map(|c| {
-                    Pixel::from_char(c).ok_or(ParseImageError::InvalidPixel(c))
-                })
-            })
-            .collect::<Result<Vec<Pixel>, ParseImageError>>()?;
-
-        // IMPROVEMENT: check for InconsistentRowLength before collecting
-        size.0 = pixels.len() / size.1;
-        if size.0 * size.1 != pixels.len() { return Err(ParseImageError::IncosistentRowLength); }
+        // the image starts after the algorithm and an empty line
+        let pixels = Grid::parse_with(pixels, parse_pixel).map_err(|e| e.offset_lines(2))?;
         let buffer = Vec::new();
 
         Ok(Image {
-            pixels, buffer, algorithm, size, padding_pixel: Pixel::Dark
+            pixels, buffer, algorithm, padding_pixel: Pixel::Dark
         })
     }
 }
@@ -127,50 +98,34 @@ impl Image {
     }
 
     fn enhance(&mut self) {
+        let (height, width) = (self.pixels.height(), self.pixels.width());
         self.buffer.clear();
-        if let Some(additional) = (self.pixels.len() + 2*(self.size.0 + self.size.1) + 4).checked_sub(self.buffer.capacity()) {
-            self.buffer.reserve(additional);
-        }
+        self.buffer.reserve((height+2) * (width+2));
         let padding_bit = if self.padding_pixel == Pixel::Light { 1 } else { 0 };
 
+        // the bit of the pixel at row `i` (if it is not above the image) and column `j`, where pixels outside the
+        // image are padding
+        let pixels = &self.pixels;
+        let bit = |i: Option<usize>, j: usize| -> usize {
+            match i.and_then(|i| pixels.get((i, j))) {
+                Some(&pixel) => if pixel == Pixel::Light { 1 } else { 0 },
+                None => padding_bit,
+            }
+        };
+
         // generate the pixels of the new enhanced image row-by-row, from left to right
         let mut idx;    // index into the algorithm look-up table
-        for i in 0..(self.size.0+2) {   // iterate over the rows of the new image
+        for i in 0..(height+2) {   // iterate over the rows of the new image
             idx = if padding_bit == 1 { 0b111111111 } else { 0 };
-            for j in 0..self.size.1 {   // iterate over the columns of the new image (ignoring the last two columns)
+            for j in 0..width {   // iterate over the columns of the new image (ignoring the last two columns)
                 idx <<= 1;
                 idx &= 0b110110110;
 
-                // out-of-bounds pixels will be handled in the else block (using `padding_bit`)
-                // ! The bottom two pixels need extra checks to check if they are within the lower bounds.
-                //   Instead of doing `checked_sub`, since the index is guaranteed to be positive (note
-                //   that this is not true for pixels outside the lower bound, thus we need to check `i`
-                //   first for that case), we can just try to `get` the pixel. If the pixels is not found,
-                //   then it is out of bounds.
-
-                // fix upper-right bit
-                if i > 1 {
-                    if self.pixels[self.size.1 * (i-2) + j] == Pixel::Light {
-                        idx |= 0b1000000;
-                    }
-                } else { idx |= padding_bit << 6; }
-
-                // fix right bit
-                let pixel_get = if i > 0 {
-                    self.pixels.get(self.size.1 * (i-1) + j)
-                } else { None };
-                if let Some(pixel) = pixel_get {
-                    if *pixel == Pixel::Light {
-                        idx |= 0b1000;
-                    }
-                } else { idx |= padding_bit << 3; }
-
-                // fix lower-right bit
-                if let Some(pixel) = self.pixels.get(self.size.1 * i + j) {
-                    if *pixel == Pixel::Light {
-                        idx |= 0b1;
-                    }
-                } else { idx  |= padding_bit; }
+                // fix the upper-right, right and lower-right bits, which are the pixels in column `j` of the rows
+                // `i-2`, `i-1` and `i` of the current image
+                idx |= bit(i.checked_sub(2), j) << 6;
+                idx |= bit(i.checked_sub(1), j) << 3;
+                idx |= bit(Some(i), j);
 
                 self.buffer.push(self.algorithm[idx]);
             }
@@ -185,9 +140,9 @@ impl Image {
             self.buffer.push(self.algorithm[idx]);
         }
 
-        self.size.0 += 2;
-        self.size.1 += 2;
-        mem::swap(&mut self.buffer, &mut self.pixels);
+        let enhanced = Grid::from_vec(height+2, width+2, mem::take(&mut self.buffer))
+            .expect("a row is generated for every row of the enhanced image");
+        self.buffer = mem::replace(&mut self.pixels, enhanced).into_vec();
 
         match self.padding_pixel {
             Pixel::Light => self.padding_pixel = self.algorithm[0b111111111],
@@ -197,24 +152,41 @@ impl Image {
 }
 impl Display for Image {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        for (i, pixel) in self.pixels.iter().enumerate() {
-            write!(f, "{}", pixel)?;
-            if (i+1) % (self.size.1) == 0 {
-                writeln!(f)?;
-            }
-        }
-        Ok(())
+        write!(f, "{}", self.pixels)
     }
 }
 
 
 
 
-pub fn day20_main(file_data: &str) -> (usize, usize) {
-    let mut image = file_data.parse::<Image>()
-        .unwrap_or_else(|e| {
-            panic!("error parsing the provided image: {}", e);
-        });
+pub struct Day20;
+impl Solution for Day20 {
+    type Input = Image;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        file_data.parse::<Image>()
+    }
+
+    fn part1(image: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        let mut image = image.clone();
+        image.enhance();
+        image.enhance();
+        Ok(image.count_light_pixels())
+    }
+
+    fn part2(image: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        let mut image = image.clone();
+        for _ in 0..50 {
+            image.enhance();
+        }
+        Ok(image.count_light_pixels())
+    }
+}
+
+pub fn day20_main(file_data: &str) -> Result<(usize, usize), PuzzleError> {
+    let mut image = Day20::parse(file_data)?;
 
     // Part 1
     image.enhance();
@@ -231,7 +203,7 @@ pub fn day20_main(file_data: &str) -> (usize, usize) {
     println!("[Part 1] After enhancing the image twice, the image has {} lit pixels.", part1_ans);
     println!("[Part 1] After enhancing the image 50 times, the image has {} lit pixels.", part2_ans);
 
-    (part1_ans, part2_ans)
+    Ok((part1_ans, part2_ans))
 }
 
 
@@ -250,6 +222,6 @@ mod tests {
             ..#..
             ..###";
         println!("{}", test_data.parse::<Image>().unwrap());
-        assert_eq!(day20_main(test_data), (35, 3351));
+        assert_eq!(day20_main(test_data).unwrap(), (35, 3351));
     }
 }
\ No newline at end of file
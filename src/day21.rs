@@ -3,9 +3,11 @@ use std::cmp::Ordering;
 use std::str::FromStr;
 use std::error::Error;
 use std::fmt::{self, Display};
+use crate::{Extra, Extras, Solution, PuzzleError};
 
 #[derive(Debug)]
-enum ParsePlayerError {
+#[allow(clippy::enum_variant_names)]
+pub enum ParsePlayerError {
     InvalidPosition, InvalidId, InvalidFormat
 }
 impl Display for ParsePlayerError {
@@ -57,48 +59,42 @@ enum Roller {
 }
 
 #[derive(Debug)]
-enum ParseGameStateError {
-    IncorrectNumberOfPlayers, ParsePlayerError(ParsePlayerError), RepeatedId
+pub enum ParseGameStateError {
+    IncorrectNumberOfPlayers, RepeatedId
 }
 impl Display for ParseGameStateError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ParseGameStateError::IncorrectNumberOfPlayers => write!(f, "there must be exactly 2 players"),
-            ParseGameStateError::ParsePlayerError(e) => write!(f, "ParsePlayerError ({})", e),
             ParseGameStateError::RepeatedId => write!(f, "a player id was repeated"),
         }
     }
 }
-impl From<ParsePlayerError> for ParseGameStateError {
-    fn from(e: ParsePlayerError) -> Self {
-        ParseGameStateError::ParsePlayerError(e)
-    }
-}
 impl Error for ParseGameStateError {}
 
 #[derive(Debug, Clone)]
-struct GameState {
+pub struct GameState {
     player1: Player,
     player2: Player,
 }
 impl FromStr for GameState {
-    type Err = ParseGameStateError;
+    type Err = PuzzleError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
+        let mut lines = s.trim_end().lines();
         if let (Some(p1), Some(p2), None) = (lines.next(), lines.next(), lines.next()) {
-            let player1 = p1.trim().parse::<Player>()?;
-            let player2 = p2.trim().parse::<Player>()?;
+            let player1 = p1.trim().parse::<Player>().map_err(|e| PuzzleError::at_line(1, e))?;
+            let player2 = p2.trim().parse::<Player>().map_err(|e| PuzzleError::at_line(2, e))?;
 
             if player1.id == player2.id {
-                return Err(ParseGameStateError::RepeatedId);
+                return Err(PuzzleError::at_line(2, ParseGameStateError::RepeatedId));
             }
 
             return Ok(GameState {
                 player1, player2
             })
         }
-        Err(ParseGameStateError::IncorrectNumberOfPlayers)
+        Err(PuzzleError::invalid(ParseGameStateError::IncorrectNumberOfPlayers))
     }
 }
 impl GameState {
@@ -120,7 +116,8 @@ impl GameState {
         }
     }
 
-    fn play_dirac(&self) -> Option<(u8, usize)> {
+    // returns the number of universes in which player 1 and player 2 win respectively
+    fn count_dirac_wins(&self) -> (usize, usize) {
         let mut player1_wins = 0;
         let mut player2_wins = 0;
         let mut stack =  Vec::new();
@@ -165,6 +162,11 @@ impl GameState {
             }
         }
 
+        (player1_wins, player2_wins)
+    }
+
+    fn play_dirac(&self) -> Option<(u8, usize)> {
+        let (player1_wins, player2_wins) = self.count_dirac_wins();
         match player1_wins.cmp(&player2_wins) {
             Ordering::Equal => None,
             Ordering::Greater => Some((self.player1.id, player1_wins)),
@@ -173,11 +175,52 @@ impl GameState {
     }
 }
 
-pub fn day21_main(file_data: &str) -> (usize, Option<(u8, usize)>) {
-    let game = file_data.parse::<GameState>()
-        .unwrap_or_else(|e| {
-            panic!("error parsing the game state: {}", e);
-        });
+/// The number of universes won by the player who wins the most, displayed as that number.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DiracWins {
+    pub winner_id: Option<u8>,  // `None` if both players win the same number of times
+    pub wins: usize,
+}
+impl Display for DiracWins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.wins)
+    }
+}
+
+pub struct Day21;
+impl Solution for Day21 {
+    type Input = GameState;
+    type Answer1 = usize;
+    type Answer2 = DiracWins;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        file_data.parse::<GameState>()
+    }
+
+    fn part1(game: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(game.clone().play_deterministic())
+    }
+
+    fn part2(game: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        let (player1_wins, player2_wins) = game.count_dirac_wins();
+        let winner_id = match player1_wins.cmp(&player2_wins) {
+            Ordering::Equal => None,
+            Ordering::Greater => Some(game.player1.id),
+            Ordering::Less => Some(game.player2.id),
+        };
+        Ok(DiracWins { winner_id, wins: player1_wins.max(player2_wins) })
+    }
+
+    fn part2_extras(wins: &Self::Answer2) -> Extras {
+        match wins.winner_id {
+            Some(id) => vec![("winner_id", Extra::Int(id.into()))],
+            None => Vec::new(),
+        }
+    }
+}
+
+pub fn day21_main(file_data: &str) -> Result<(usize, Option<(u8, usize)>), PuzzleError> {
+    let game = Day21::parse(file_data)?;
 
     // Part 1
     let part1_ans = game.clone().play_deterministic();
@@ -191,12 +234,11 @@ pub fn day21_main(file_data: &str) -> (usize, Option<(u8, usize)>) {
         println!("[Part 2] Both players win the same number of times.");
     }
 
-    (part1_ans, part2_ans)
+    Ok((part1_ans, part2_ans))
 }
 
 
 #[cfg(test)]
-
 mod test {
     use super::*;
 
@@ -206,6 +248,9 @@ mod test {
             "Player 1 starting position: 4
             Player 2 starting position: 8";
 
-        assert_eq!(day21_main(test_data), (739785, Some((1, 444356092776315))));
+        assert_eq!(day21_main(test_data).unwrap(), (739785, Some((1, 444356092776315))));
+
+        let wins = Day21::part2(&Day21::parse(test_data).unwrap()).unwrap();
+        assert_eq!(Day21::part2_extras(&wins), vec![("winner_id", Extra::Int(1))]);
     }
 }
\ No newline at end of file
@@ -1,117 +1,390 @@
-use std::process;
+use std::fmt;
+use std::error::Error;
+use crate::bigint::BigUint;
+use crate::{read_lines, Solution, PuzzleError};
 
-type BinaryWord = Vec<bool>;
+/// A fixed number of bits packed into 64-bit blocks, where bit `i` is bit `i % 64` of block `i / 64`.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct BitSet {
+    blocks: Vec<u64>,
+    len: usize,
+}
+impl BitSet {
+    /// A set of `len` bits which are all 0.
+    pub fn new(len: usize) -> Self {
+        BitSet { blocks: vec![0; len.div_ceil(64)], len }
+    }
+
+    /// A set of `len` bits which are all 1.
+    pub fn filled(len: usize) -> Self {
+        let mut bits = BitSet { blocks: vec![u64::MAX; len.div_ceil(64)], len };
+        bits.clear_unused();
+        bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn blocks(&self) -> &[u64] {
+        &self.blocks
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "bit {} is outside a set of {} bits", i, self.len);
+        (self.blocks[i / 64] >> (i % 64)) & 1 == 1
+    }
 
-enum BitCriteria {
-    Oxygen, Co2
+    pub fn set(&mut self, i: usize, value: bool) {
+        assert!(i < self.len, "bit {} is outside a set of {} bits", i, self.len);
+        let mask = 1 << (i % 64);
+        if value { self.blocks[i / 64] |= mask; }
+        else { self.blocks[i / 64] &= !mask; }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.blocks.iter().map(|block| block.count_ones() as usize).sum()
+    }
+
+    /// The number of bits which are 1 in both `self` and `mask`.
+    pub fn count_ones_in(&self, mask: &BitSet) -> usize {
+        self.blocks.iter().zip(&mask.blocks).map(|(a, b)| (a & b).count_ones() as usize).sum()
+    }
+
+    /// Clears every bit of `self` where the same bit of `other` is not `value`.
+    pub fn retain_where(&mut self, other: &BitSet, value: bool) {
+        for (a, &b) in self.blocks.iter_mut().zip(&other.blocks) {
+            *a &= if value { b } else { !b };
+        }
+    }
+
+    /// Every bit flipped.
+    pub fn complement(&self) -> BitSet {
+        let mut bits = BitSet { blocks: self.blocks.iter().map(|block| !block).collect(), len: self.len };
+        bits.clear_unused();
+        bits
+    }
+
+    /// The index of the first bit which is 1.
+    pub fn first_one(&self) -> Option<usize> {
+        self.blocks.iter().enumerate()
+            .find(|(_, &block)| block != 0)
+            .map(|(i, block)| i*64 + block.trailing_zeros() as usize)
+    }
+
+    /// The index of the last bit which is 1.
+    pub fn last_one(&self) -> Option<usize> {
+        self.blocks.iter().enumerate().rev()
+            .find(|(_, &block)| block != 0)
+            .map(|(i, block)| i*64 + 63 - block.leading_zeros() as usize)
+    }
+
+    // the bits past `len` in the last block are kept at 0, so whole blocks can be counted and compared
+    fn clear_unused(&mut self) {
+        if !self.len.is_multiple_of(64) {
+            if let Some(last) = self.blocks.last_mut() { *last &= (1 << (self.len % 64)) - 1; }
+        }
+    }
+}
+
+/// A diagnostic word, where the leftmost bit of the word is the most significant bit of the set.
+pub type BinaryWord = BitSet;
+
+/// Which bit the remaining words must have at each position to be kept, given how many of them have a 1 there.
+pub enum BitCriteria {
+    Oxygen, Co2, Custom(fn(usize, usize) -> bool)
 }
 impl BitCriteria {
-    fn get_bit_to_match(&self, one_count: usize, num_of_words: usize) -> bool {
+    pub fn get_bit_to_match(&self, one_count: usize, num_of_words: usize) -> bool {
         match self {
-            BitCriteria::Oxygen => return one_count >= (num_of_words+1)/2,
-            BitCriteria::Co2 => return one_count < (num_of_words+1)/2
+            BitCriteria::Oxygen => one_count >= num_of_words.div_ceil(2),
+            BitCriteria::Co2 => one_count < num_of_words.div_ceil(2),
+            BitCriteria::Custom(f) => f(one_count, num_of_words),
         }
     }
 }
 
-pub fn day3_main(file_data: &str) {
-    // parse to Vec<BinaryWord>
-    let (word_len, binary_words) = parse_to_binword_vec(&file_data)
-        .unwrap_or_else(|e| {
-            if e == 0 {
-                eprintln!("File is empty!");
+#[derive(Debug)]
+pub enum ParseBinaryWordError {
+    EmptyReport, InvalidLength { expected: usize, found: usize }, InvalidBit(char)
+}
+impl fmt::Display for ParseBinaryWordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseBinaryWordError::EmptyReport => write!(f, "no diagnostic words"),
+            ParseBinaryWordError::InvalidLength { expected, found } =>
+                write!(f, "expected a word of length {}, found length {}", expected, found),
+            ParseBinaryWordError::InvalidBit(c) => write!(f, "invalid bit '{}'", c),
+        }
+    }
+}
+impl Error for ParseBinaryWordError {}
+
+pub struct DiagnosticReport {
+    word_len: usize,
+    words: Vec<BinaryWord>,
+    // column `j` has bit `i` set if the `j`th bit from the left of word `i` is 1
+    columns: Vec<BitSet>,
+}
+impl DiagnosticReport {
+    fn new(word_len: usize, words: Vec<BinaryWord>) -> Self {
+        let mut columns = vec![BitSet::new(words.len()); word_len];
+        for (i, word) in words.iter().enumerate() {
+            for (j, column) in columns.iter_mut().enumerate() {
+                column.set(i, word.get(word_len - 1 - j));
+            }
+        }
+        DiagnosticReport { word_len, words, columns }
+    }
+
+    pub fn words(&self) -> &[BinaryWord] {
+        &self.words
+    }
+}
+
+/// The words of a report in a binary trie, from their leftmost bit down, where each node counts the words below it.
+/// A word only has nodes down to where it parts from every other word, so there are about two nodes per word rather
+/// than one per bit. Once built, the word matching any `BitCriteria` is found in a single walk from the root.
+pub struct WordTrie {
+    nodes: Vec<TrieNode>,   // the root is the first node
+}
+struct TrieNode {
+    children: [Option<usize>; 2],   // none for a leaf, which holds a single word or the last bit of repeated words
+    count: usize,
+    last_word: usize,   // the index of the last word below the node
+}
+impl WordTrie {
+    pub fn new(report: &DiagnosticReport) -> Self {
+        let mut nodes = vec![TrieNode { children: [None, None], count: 0, last_word: 0 }];
+        let add_child = |nodes: &mut Vec<TrieNode>, node: usize, bit: usize, word: usize| {
+            nodes.push(TrieNode { children: [None, None], count: 0, last_word: word });
+            nodes[node].children[bit] = Some(nodes.len() - 1);
+            nodes.len() - 1
+        };
+        for (i, word) in report.words.iter().enumerate() {
+            let mut node = 0;
+            let mut bits = (0..report.word_len).rev();
+            loop {
+                let current = &mut nodes[node];
+                current.count += 1;
+                let previous_word = std::mem::replace(&mut current.last_word, i);
+                // the first word to reach a node stays there until a second one does
+                if current.count == 1 { break; }
+                let j = match bits.next() {
+                    Some(j) => j,
+                    None => break,
+                };
+                if current.count == 2 && current.children == [None, None] {
+                    let child = add_child(&mut nodes, node, report.words[previous_word].get(j) as usize, previous_word);
+                    nodes[child].count = 1;
+                }
+                let bit = word.get(j) as usize;
+                node = match nodes[node].children[bit] {
+                    Some(child) => child,
+                    None => add_child(&mut nodes, node, bit, i),
+                };
             }
-            else {
-                eprintln!("Error parsing line {}: invalid format or length", e+1);
+        }
+        WordTrie { nodes }
+    }
+
+    /// The index of the word which `bit_criteria` keeps, or `None` if there are no words. As when filtering, the last
+    /// remaining word is kept if no word has the bit to match.
+    pub fn find(&self, bit_criteria: &BitCriteria) -> Option<usize> {
+        let mut node = &self.nodes[0];
+        if node.count == 0 { return None; }
+        loop {
+            if node.children == [None, None] { return Some(node.last_word); }
+            let one_count = node.children[1].map_or(0, |child| self.nodes[child].count);
+            let bit_to_match = bit_criteria.get_bit_to_match(one_count, node.count);
+            match node.children[bit_to_match as usize] {
+                None => return Some(node.last_word),
+                Some(child) if self.nodes[child].count == 1 => return Some(self.nodes[child].last_word),
+                Some(child) => node = &self.nodes[child],
             }
-            process::exit(1);
-        });
+        }
+    }
+}
+
+pub struct Day3;
+impl Solution for Day3 {
+    type Input = DiagnosticReport;
+    type Answer1 = BigUint;
+    type Answer2 = BigUint;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        // parse to Vec<BinaryWord>
+        let (word_len, words) = parse_to_binword_vec(file_data)?;
+        Ok(DiagnosticReport::new(word_len, words))
+    }
+
+    fn part1(report: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        let (gamma, epsilon) = power_rates(report);
+        Ok(&gamma * &epsilon)
+    }
+
+    fn part2(report: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        Ok(life_support_rating(report))
+    }
+}
 
+fn power_rates(report: &DiagnosticReport) -> (BigUint, BigUint) {
     // find most common bit in each column
-    let most_common_bit = binary_words
-        .iter()
-        .fold(vec![0; word_len], |mut acc, word| {
-            word.iter().enumerate().for_each(|(j, c)| {
-                match c {
-                    false => acc[j] -= 1,
-                    true => acc[j] += 1
-                }
-            });
-            acc
-        })
-        .into_iter()
-        .map(|count| count > 0)
-        .collect::<Vec<bool>>();
+    let mut gamma = BitSet::new(report.word_len);
+    for (j, column) in report.columns.iter().enumerate() {
+        gamma.set(report.word_len - 1 - j, 2*column.count_ones() > report.words.len());
+    }
+    let epsilon = gamma.complement();
+    (word_value(&gamma), word_value(&epsilon))
+}
+
+fn life_support_ratings(report: &DiagnosticReport) -> (BigUint, BigUint) {
+    let oxygen_rating = match_bit_criteria(report, &BitCriteria::Oxygen).unwrap();
+    let co2_rating = match_bit_criteria(report, &BitCriteria::Co2).unwrap();
+    (word_value(oxygen_rating), word_value(co2_rating))
+}
+
+/// The product of the oxygen generator and CO2 scrubber ratings, found by filtering the words one column at a time.
+pub fn life_support_rating(report: &DiagnosticReport) -> BigUint {
+    let (oxygen_rating, co2_rating) = life_support_ratings(report);
+    &oxygen_rating * &co2_rating
+}
+
+/// Like `life_support_rating`, but the ratings are found by walking a `WordTrie` of the words.
+pub fn life_support_rating_trie(report: &DiagnosticReport) -> BigUint {
+    let trie = WordTrie::new(report);
+    let rating = |bit_criteria| word_value(&report.words[trie.find(&bit_criteria).unwrap()]);
+    &rating(BitCriteria::Oxygen) * &rating(BitCriteria::Co2)
+}
+
+pub fn day3_main(file_data: &str) -> Result<(BigUint, BigUint), PuzzleError> {
+    let report = Day3::parse(file_data)?;
 
     // Part 1
-    let gamma = bits_to_u16(&most_common_bit);
-    let epsilon = !gamma & ((0x1 << word_len) - 0x1);   // mask irrelevant bits after bitwise not
+    let (gamma, epsilon) = power_rates(&report);
     println!("Gamma Rate = {}, Epsilon Rate = {}", gamma, epsilon);
-    println!("The power consumption of the submarine is {}!", (gamma as u32) * (epsilon as u32));
+    let power_consumption = &gamma * &epsilon;
+    println!("The power consumption of the submarine is {}!", power_consumption);
 
     // Part 2
-    let oxygen_rating = match_bit_criteria(binary_words.clone(), word_len, BitCriteria::Oxygen).unwrap();
-    let oxygen_rating = bits_to_u16(&oxygen_rating);
-    let co2_rating = match_bit_criteria(binary_words, word_len, BitCriteria::Co2).unwrap();
-    let co2_rating = bits_to_u16(&co2_rating);
+    let (oxygen_rating, co2_rating) = life_support_ratings(&report);
     println!("\nOxygen Rating: {}, CO2 Rating: {}", oxygen_rating, co2_rating);
-    println!("The life support rating of the submarine is {}!", (oxygen_rating as u32) * (co2_rating as u32));
+    let life_support_rating = &oxygen_rating * &co2_rating;
+    println!("The life support rating of the submarine is {}!", life_support_rating);
+
+    Ok((power_consumption, life_support_rating))
 }
 
-fn str_to_binword(s: &str) -> Option<BinaryWord> {
-    s.chars().map(|c| {
+// on failure, returns the index of the invalid character along with the error
+fn str_to_binword(s: &str) -> Result<BinaryWord, (usize, ParseBinaryWordError)> {
+    let mut word = BitSet::new(s.len());
+    for (j, c) in s.chars().enumerate() {
         match c {
-            '0' => Some(false),
-            '1' => Some(true),
-            _ => None,
+            '0' => (),
+            '1' => word.set(s.len() - 1 - j, true),
+            _ => return Err((j, ParseBinaryWordError::InvalidBit(c))),
         }
-    })
-    .collect::<Option<BinaryWord>>()
+    }
+    Ok(word)
 }
 
-fn parse_to_binword_vec(data_string: &str) -> Result<(usize, Vec<BinaryWord>), usize> {
-    let word_len = data_string
-        .lines().nth(0)
-        .ok_or_else(|| 0 as u16)?.len();
-    let word_vec = data_string
-        .lines().enumerate()
+fn parse_to_binword_vec(data_string: &str) -> Result<(usize, Vec<BinaryWord>), PuzzleError> {
+    // trailing blank lines are skipped, so the first line is only blank when the report is empty
+    let lines = read_lines(data_string.as_bytes()).collect::<Result<Vec<_>, _>>()?;
+    let word_len = match lines.first() {
+        Some((_, s)) if !s.is_empty() => s.len(),
+        _ => return Err(PuzzleError::invalid(ParseBinaryWordError::EmptyReport)),
+    };
+    let word_vec = lines.iter()
         .map(|(i, s)| {
-            if s.len() != word_len { return Err(i as usize); }
-            str_to_binword(s).ok_or_else(|| i as usize)
+            if s.len() != word_len {
+                let e = ParseBinaryWordError::InvalidLength { expected: word_len, found: s.len() };
+                return Err(PuzzleError::at_line(*i, e));
+            }
+            str_to_binword(s).map_err(|(j, e)| PuzzleError::at(*i, j+1, e))
         })
-        .collect::<Result<Vec<BinaryWord>, usize>>()?;
+        .collect::<Result<Vec<BinaryWord>, _>>()?;
     Ok((word_len, word_vec))
 }
 
-fn bits_to_u16(binary_word: &[bool]) -> u16 {
-    let word_len = binary_word.len();
-    binary_word.iter().enumerate()
-    .fold(0, |acc, (i, is_one)| {
-        acc + ((*is_one as u16) << (word_len - 1 - i))
-    })
-}
-
-fn match_bit_criteria<'a>(mut words: Vec<BinaryWord>, word_len: usize, bit_criteria: BitCriteria)
--> Option<BinaryWord> {
-    if word_len == 0 || words.len() == 0 { return None; }
-    for j in 0..word_len {
-        let last_word = words.last().unwrap().clone();
-        let one_count = words
-            .iter()
-            .fold(0, |acc, word| {
-                acc + (*word.iter().nth(j).unwrap() as usize)
-            });
-        let bit_to_match = bit_criteria.get_bit_to_match(one_count, words.len());
-
-        words.retain(|word| {
-            *word.iter().nth(j).unwrap() == bit_to_match
-        });
-
-        match words.len() {
-            0 => return Some(last_word.clone()),
-            1 => return Some(words.pop().unwrap()),
+fn word_value(word: &BinaryWord) -> BigUint {
+    BigUint::from_limbs(word.blocks().to_vec())
+}
+
+// the remaining words are tracked as a set of word indices, so the ones in each column are counted with popcount
+fn match_bit_criteria<'a>(report: &'a DiagnosticReport, bit_criteria: &BitCriteria) -> Option<&'a BinaryWord> {
+    if report.word_len == 0 || report.words.is_empty() { return None; }
+    let mut remaining = BitSet::filled(report.words.len());
+    for column in &report.columns {
+        let last_word = remaining.last_one()?;
+        let one_count = column.count_ones_in(&remaining);
+        let bit_to_match = bit_criteria.get_bit_to_match(one_count, remaining.count_ones());
+
+        remaining.retain_where(column, bit_to_match);
+
+        match remaining.count_ones() {
+            0 => return Some(&report.words[last_word]),
+            1 => return remaining.first_one().map(|i| &report.words[i]),
             _ => ()
         }
     }
-    Some(words.pop().unwrap())
-}
\ No newline at end of file
+    remaining.last_one().map(|i| &report.words[i])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn it_works() {
+        let test_data = "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010";
+        let (part1_ans, part2_ans) = day3_main(test_data).unwrap();
+        assert_eq!(part1_ans, BigUint::from(198u64));
+        assert_eq!(part2_ans, BigUint::from(230u64));
+
+        let report = Day3::parse(test_data).unwrap();
+        assert_eq!(life_support_rating_trie(&report), part2_ans);
+        let trie = WordTrie::new(&report);
+        assert_eq!(trie.nodes.len(), 23);  // rather than 38 with a node for every prefix
+        for bit_criteria in [BitCriteria::Custom(|_, _| true), BitCriteria::Custom(|ones, words| 2*ones < words)] {
+            let expected = match_bit_criteria(&report, &bit_criteria);
+            assert_eq!(trie.find(&bit_criteria).map(|i| &report.words()[i]), expected);
+        }
+
+        // narrow random reports have repeated words, which both methods must handle alike
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..100 {
+            let (word_len, word_count) = (rng.gen_range(1..=6), rng.gen_range(1..=40));
+            let words = (0..word_count)
+                .map(|_| (0..word_len).map(|_| if rng.gen() { '1' } else { '0' }).collect::<String>())
+                .collect::<Vec<_>>();
+            let report = Day3::parse(&words.join("\n")).unwrap();
+            assert_eq!(life_support_rating_trie(&report), life_support_rating(&report), "{:?}", words);
+        }
+
+        // words wider than any primitive integer
+        let wide_data = format!("1{}\n1{}\n0{}", "0".repeat(99), "1".repeat(99), "1".repeat(99));
+        let report = Day3::parse(&wide_data).unwrap();
+        let (gamma, epsilon) = power_rates(&report);
+        assert_eq!((gamma.bits(), epsilon.bits()), (100, 0));
+        assert_eq!(Day3::part2(&report).unwrap().bits(), 199);
+        assert_eq!(WordTrie::new(&report).nodes.len(), 5);
+        assert_eq!(life_support_rating_trie(&report), Day3::part2(&report).unwrap());
+
+        // trailing blank lines are skipped, but an empty report or a blank line between words is an error
+        let report = Day3::parse(&format!("{}\n\n\n", test_data)).unwrap();
+        assert_eq!(Day3::part2(&report).unwrap(), part2_ans);
+        for empty_data in ["", "\n", "\n\n"] {
+            let e = Day3::parse(empty_data).err().unwrap();
+            assert_eq!(e.to_string(), PuzzleError::invalid(ParseBinaryWordError::EmptyReport).to_string());
+        }
+        assert!(Day3::parse("00100\n\n11110").is_err());
+    }
+}
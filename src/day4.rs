@@ -1,135 +1,344 @@
-use std::process;
+use std::fmt;
+use std::error::Error;
+use std::num::ParseIntError;
+use std::collections::HashMap;
+use rand::Rng;
+use rand::seq::{index, SliceRandom};
+use crate::grid::{Grid, ParseGridError, Point};
+use crate::{Solution, PuzzleError, column_of};
 
-#[derive(Clone)]
-struct BingoBoard(Vec<u8>);
+#[derive(Debug)]
+pub enum ParseBingoError {
+    EmptyInput, InvalidNumber(ParseIntError)
+}
+impl fmt::Display for ParseBingoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseBingoError::EmptyInput => write!(f, "the input is empty"),
+            ParseBingoError::InvalidNumber(_) => write!(f, "invalid bingo number"),
+        }
+    }
+}
+impl Error for ParseBingoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseBingoError::InvalidNumber(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A way for a board to win, by marking every number of one of its lines.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WinPattern {
+    Row, Column,
+    Diagonal,       // either of the two diagonals of a square board, which non-square boards don't have
+    FourCorners,
+    Blackout,       // every number on the board
+}
+impl WinPattern {
+    // the groups of positions on a `height`x`width` board which win when all of them are marked
+    fn lines(self, height: usize, width: usize) -> Vec<Vec<Point>> {
+        match self {
+            WinPattern::Row => (0..height).map(|i| (0..width).map(|j| (i, j)).collect()).collect(),
+            WinPattern::Column => (0..width).map(|j| (0..height).map(|i| (i, j)).collect()).collect(),
+            WinPattern::Diagonal if height == width => vec![
+                (0..height).map(|i| (i, i)).collect(),
+                (0..height).map(|i| (i, width-1-i)).collect(),
+            ],
+            WinPattern::Diagonal => Vec::new(),
+            WinPattern::FourCorners => vec![vec![(0, 0), (0, width-1), (height-1, 0), (height-1, width-1)]],
+            WinPattern::Blackout => vec![(0..height).flat_map(|i| (0..width).map(move |j| (i, j))).collect()],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BingoBoard {
+    numbers: Grid<u32>,
+    marked: Grid<bool>,
+}
 impl BingoBoard {
-    fn new(board_str: &str) -> Result<Self, usize> {
-        let board = board_str.split_whitespace().enumerate()
-        .map(|(i, s)| {
-            s.parse::<u8>().map_err(|_| i)
-        })
-        .collect::<Result<Vec<u8>, usize>>()?;
-        if board.len() != 25 { return Err(25); }
-        Ok(BingoBoard(board))
+    /// A board of `numbers`, which must have at least one cell.
+    pub fn new(numbers: Grid<u32>) -> Result<Self, ParseGridError> {
+        if numbers.height() == 0 || numbers.width() == 0 { return Err(ParseGridError::EmptyGrid); }
+        let marked = Grid::new(numbers.height(), numbers.width(), false);
+        Ok(BingoBoard { numbers, marked })
+    }
+
+    pub fn numbers(&self) -> &Grid<u32> {
+        &self.numbers
     }
 
-    fn mark(&mut self, num: u8) -> bool {
+    // errors are located relative to the first line of `board_str`
+    fn parse(board_str: &str) -> Result<Self, PuzzleError> {
+        let mut numbers = Vec::new();
+        let mut width = None;
+        let mut height = 0;
+        for (i, line) in board_str.lines().enumerate() {
+            let row_start = numbers.len();
+            for s in line.split_whitespace() {
+                let num = s.parse::<u32>().map_err(|e| {
+                    PuzzleError::at(i+1, column_of(line, s), ParseBingoError::InvalidNumber(e))
+                })?;
+                numbers.push(num);
+            }
+
+            let row_len = numbers.len() - row_start;
+            match width {
+                None => width = Some(row_len),
+                Some(expected) if expected != row_len => {
+                    return Err(PuzzleError::at_line(i+1, ParseGridError::InvalidRowLength { expected, found: row_len }));
+                },
+                _ => (),
+            }
+            height += 1;
+        }
+
+        let numbers = Grid::from_vec(height, width.unwrap_or(0), numbers).expect("every row has `width` numbers");
+        BingoBoard::new(numbers).map_err(|e| PuzzleError::at_line(1, e))
+    }
+
+    fn mark(&mut self, num: u32) -> bool {
         let mut is_marked = false;
-        for i in 0..25 {
-            if self.0[i] == num {
-                self.0[i] = u8::MAX;
+        for (marked, &x) in self.marked.iter_mut().zip(self.numbers.iter()) {
+            if x == num {
+                *marked = true;
                 is_marked = true;
             }
         }
         is_marked
     }
 
-    fn check(&self) -> bool {
-        for i in 0..5 {
-            let mut row_i = self.0.iter().skip(5*i).take(5);
-            if row_i.all(|x| *x == u8::MAX) { return true; }
-
-            let mut col_i = self.0.iter().skip(i).step_by(5);
-            if col_i.all(|x| *x == u8::MAX) { return true; }
-        }
-        false
+    fn check(&self, lines: &[Vec<Point>]) -> bool {
+        lines.iter().any(|line| line.iter().all(|&p| self.marked[p]))
     }
 
-    fn mark_and_check(&mut self, num: u8) -> bool {
+    fn mark_and_check(&mut self, num: u32, lines: &[Vec<Point>]) -> bool {
         if !self.mark(num) { return false; }
-        self.check()
+        self.check(lines)
     }
 
-    fn sum_unmarked(&self) -> u16 {
-        self.0.iter().fold(0, |acc, x| {
-            if *x == u8::MAX { return acc; }
-            acc + (*x as u16)
-        })
+    fn sum_unmarked(&self) -> u64 {
+        self.numbers.iter().zip(self.marked.iter())
+            .filter(|(_, &marked)| !marked)
+            .map(|(&x, _)| u64::from(x))
+            .sum()
     }
 }
 
-pub fn day4_main(file_data: &str) -> (u16, u16) {
-    // split file into blocks using 2 new lines
-    let block_delimiter;
-    if file_data.chars().nth(file_data.lines().nth(0).unwrap_or_else(|| {
-        eprintln!("File is empty!");
-        process::exit(1);
-    }).len()).unwrap() == '\r' { block_delimiter = "\r\n\r\n"; }
-    else { block_delimiter = "\n\n"; }
-    let mut file_blocks = file_data.split(block_delimiter);
-
-    // get list of bingo numbers
-    let mut nums = file_blocks.next().unwrap()
-        .split(",")
-        .enumerate()
-        .map(|(i, s)| {
-            s.parse::<u8>().unwrap_or_else(|e| {
-                eprintln!("Error parsing bingo number {}: ParseIntError {{ {} }}", i, e);
-                eprintln!("Instead of u8, \"{}\" was encountered!", s);
-                process::exit(1);
+// the lines of a board size as cell indices, in a form that lets each mark update only the lines through its cell
+struct LineLayout {
+    line_lens: Vec<usize>,
+    cell_lines: Vec<Vec<usize>>,    // the indices of the lines through each cell
+}
+impl LineLayout {
+    fn new(height: usize, width: usize, patterns: &[WinPattern]) -> Self {
+        let lines = patterns.iter().flat_map(|pattern| pattern.lines(height, width)).collect::<Vec<_>>();
+        let mut cell_lines = vec![Vec::new(); height*width];
+        for (k, line) in lines.iter().enumerate() {
+            for &(i, j) in line {
+                cell_lines[i*width + j].push(k);
+            }
+        }
+        LineLayout { line_lens: lines.iter().map(Vec::len).collect(), cell_lines }
+    }
+}
+
+/// A board winning the game, as the index of the board, the index of the draw which completed it and its score.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Win {
+    pub board: usize,
+    pub draw: usize,
+    pub score: u128,
+}
+
+#[derive(Debug)]
+pub struct BingoGame {
+    nums: Vec<u32>,
+    boards: Vec<BingoBoard>,
+    patterns: Vec<WinPattern>,
+}
+impl BingoGame {
+    /// A game where boards win by completing a row or a column.
+    pub fn new(nums: Vec<u32>, boards: Vec<BingoBoard>) -> Self {
+        BingoGame { nums, boards, patterns: vec![WinPattern::Row, WinPattern::Column] }
+    }
+
+    /// Replaces the ways a board can win, where completing any one of `patterns` is enough.
+    pub fn with_patterns(mut self, patterns: &[WinPattern]) -> Self {
+        self.patterns = patterns.to_vec();
+        self
+    }
+
+    pub fn boards(&self) -> &[BingoBoard] {
+        &self.boards
+    }
+
+    /// A game of `board_count` boards of `height`x`width` distinct numbers below `max_number`, where every number
+    /// below `max_number` is drawn once in a random order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the boards have no cells, or more cells than there are numbers.
+    pub fn random<R: Rng>(rng: &mut R, board_count: usize, height: usize, width: usize, max_number: u32) -> Self {
+        let boards = (0..board_count)
+            .map(|_| {
+                let numbers = index::sample(rng, max_number as usize, height*width).into_iter()
+                    .map(|x| x as u32)
+                    .collect();
+                let numbers = Grid::from_vec(height, width, numbers).expect("the sample has a number per cell");
+                BingoBoard::new(numbers).expect("the boards must have at least one cell")
             })
-        });
-
-    // create vector of BingoBoard
-    let mut all_boards = file_blocks
-        .enumerate()
-        .map(|(i, block)| {
-            BingoBoard::new(block).unwrap_or_else(|e| {
-                eprintln!("Error parsing bingo board {} (line {}~{}):", i, 3+6*i, 7+6*i);
-                if e == 25 { eprintln!("each board can only have {} numbers", 25); }
-                else { eprintln!("invalid value in the position {} of the board", e+1); }
-                process::exit(1);
+            .collect();
+        let mut nums = (0..max_number).collect::<Vec<_>>();
+        nums.shuffle(rng);
+        BingoGame::new(nums, boards)
+    }
+
+    /// Plays until all boards have won or the numbers run out, returning the boards in the order that they won.
+    /// Boards which win on the same draw are ordered by their index.
+    pub fn play(&self) -> Vec<Win> {
+        let mut boards = self.boards.clone();
+        let lines = boards.iter()
+            .map(|board| {
+                let (height, width) = (board.numbers.height(), board.numbers.width());
+                self.patterns.iter().flat_map(|pattern| pattern.lines(height, width)).collect::<Vec<_>>()
             })
-        })
-        .collect::<Vec<BingoBoard>>();
+            .collect::<Vec<_>>();
+        let mut has_won = vec![false; boards.len()];
+        let mut wins = Vec::with_capacity(boards.len());
+
+        for (draw, &num) in self.nums.iter().enumerate() {
+            for (i, board) in boards.iter_mut().enumerate() {
+                if !has_won[i] && board.mark_and_check(num, &lines[i]) {
+                    has_won[i] = true;
+                    wins.push(Win { board: i, draw, score: u128::from(num) * u128::from(board.sum_unmarked()) });
+                }
+            }
+            if wins.len() == boards.len() { break; }
+        }
+        wins
+    }
 
+    /// Like `play`, but each drawn number is looked up in an index of the cells holding it, and each board counts the
+    /// marks on each of its lines, so the game takes time linear in the number of marks rather than the board sizes.
+    pub fn play_indexed(&self) -> Vec<Win> {
+        // boards of the same size share their layout
+        let mut layouts = Vec::new();
+        let mut layout_of_size = HashMap::new();
+        let board_layouts = self.boards.iter()
+            .map(|board| {
+                let size = (board.numbers.height(), board.numbers.width());
+                *layout_of_size.entry(size).or_insert_with(|| {
+                    layouts.push(LineLayout::new(size.0, size.1, &self.patterns));
+                    layouts.len() - 1
+                })
+            })
+            .collect::<Vec<_>>();
 
-    // Part 1
-    let mut num;
-    let mut first_is_found = false;
-    let score1 = loop {
-        num = nums.next().unwrap();
-        let mut score1 = 0;
-
-        // mark and check all boards with current bingo number, break with score if found
-        all_boards.iter_mut().enumerate().for_each(|(i, board)| {
-            if board.mark_and_check(num) && !first_is_found {
-                println!("Board {} is the first to win!", i+1);
-                score1 = (num as u16) * board.sum_unmarked();
-                first_is_found = true;
+        // the cells holding each number, ordered by board
+        let mut cells_of_num: HashMap<u32, Vec<(usize, usize)>> = HashMap::new();
+        for (i, board) in self.boards.iter().enumerate() {
+            for (cell, &x) in board.numbers.iter().enumerate() {
+                cells_of_num.entry(x).or_default().push((i, cell));
             }
-        });
-        if first_is_found { break score1; }
-    };
-    println!("The score of this board is {}.", score1);
+        }
 
+        let mut marked = self.boards.iter().map(|board| vec![false; board.numbers.len()]).collect::<Vec<_>>();
+        let mut line_hits = board_layouts.iter().map(|&l| vec![0; layouts[l].line_lens.len()]).collect::<Vec<_>>();
+        let mut unmarked_sums = self.boards.iter().map(BingoBoard::sum_unmarked).collect::<Vec<_>>();
+        let mut has_won = vec![false; self.boards.len()];
+        let mut wins = Vec::with_capacity(self.boards.len());
 
-    // Part 2
-    let last_board_completed = loop {
-        num = nums.next().unwrap();
-
-        // mark all boards, then remove all winning boards
-        all_boards.iter_mut().for_each(|board| { board.mark(num); });
-        let last_in_list_copy = (*all_boards.last().unwrap()).clone();
-        all_boards.retain(|board| !board.check());
-
-        // play the last remaning board till bingo and get score
-        if all_boards.len() ==  1 {
-            let mut last_board = all_boards.pop().unwrap();
-            while {
-                num = nums.next().unwrap();
-                !last_board.mark_and_check(num)
-            } {}
-            break last_board
+        for (draw, &num) in self.nums.iter().enumerate() {
+            let cells = match cells_of_num.get(&num) {
+                Some(cells) => cells,
+                None => continue,
+            };
+            // a board is scored only after all of its cells holding `num` are marked
+            let mut completed = Vec::new();
+            for &(i, cell) in cells {
+                if has_won[i] || marked[i][cell] { continue; }
+                marked[i][cell] = true;
+                unmarked_sums[i] -= u64::from(num);
+                let layout = &layouts[board_layouts[i]];
+                for &k in &layout.cell_lines[cell] {
+                    line_hits[i][k] += 1;
+                    if line_hits[i][k] == layout.line_lens[k] && completed.last() != Some(&i) { completed.push(i); }
+                }
+            }
+            for i in completed {
+                has_won[i] = true;
+                wins.push(Win { board: i, draw, score: u128::from(num) * u128::from(unmarked_sums[i]) });
+            }
+            if wins.len() == self.boards.len() { break; }
         }
+        wins
+    }
+}
+
+pub struct Day4;
+impl Solution for Day4 {
+    type Input = BingoGame;
+    type Answer1 = u128;
+    type Answer2 = u128;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        // split file into blocks using 2 new lines
+        let first_line = file_data.lines().next()
+            .ok_or_else(|| PuzzleError::invalid(ParseBingoError::EmptyInput))?;
+        let block_delimiter = if file_data.chars().nth(first_line.len()) == Some('\r') { "\r\n\r\n" }
+        else { "\n\n" };
+        let mut file_blocks = file_data.split(block_delimiter);
+
+        // get list of bingo numbers
+        let nums = file_blocks.next().unwrap()
+            .split(',')
+            .map(|s| {
+                s.trim().parse::<u32>().map_err(|e| {
+                    PuzzleError::at(1, column_of(first_line, s), ParseBingoError::InvalidNumber(e))
+                })
+            })
+            .collect::<Result<Vec<u32>, _>>()?;
 
-        // if no boards remain after this pass, the last board is taken to be the last to win
-        if all_boards.len() == 0 { break last_in_list_copy; }
-    };
-    let score2 = (num as u16) * last_board_completed.sum_unmarked();
-    println!("The score of the last board to win is {}.", score2);
+        // create vector of BingoBoard, where the first board starts after the line of numbers and an empty line
+        let mut line_offset = 2;
+        let boards = file_blocks
+            .map(|block| {
+                let board = BingoBoard::parse(block).map_err(|e| e.offset_lines(line_offset));
+                line_offset += block.lines().count() + 1;
+                board
+            })
+            .collect::<Result<Vec<BingoBoard>, _>>()?;
+
+        Ok(BingoGame::new(nums, boards))
+    }
+
+    fn part1(game: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        game.play_indexed().first().map(|win| win.score).ok_or_else(|| PuzzleError::no_solution("no board wins"))
+    }
 
-    (score1, score2)
+    fn part2(game: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        game.play_indexed().last().map(|win| win.score).ok_or_else(|| PuzzleError::no_solution("no board wins"))
+    }
+}
+
+pub fn day4_main(file_data: &str) -> Result<(u128, u128), PuzzleError> {
+    let game = Day4::parse(file_data)?;
+    let wins = game.play_indexed();
+
+    // Part 1
+    let first = wins.first().ok_or_else(|| PuzzleError::no_solution("no board wins"))?;
+    println!("The score of the first board to win (board {} on draw {}) is {}.",
+        first.board + 1, first.draw + 1, first.score);
+
+    // Part 2
+    let last = wins.last().unwrap();
+    println!("The score of the last board to win (board {} on draw {}) is {}.",
+        last.board + 1, last.draw + 1, last.score);
+
+    Ok((first.score, last.score))
 }
 
 
@@ -137,6 +346,8 @@ pub fn day4_main(file_data: &str) -> (u16, u16) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
 
     #[test]
     fn it_works() {
@@ -160,6 +371,44 @@ mod tests {
             18  8 23 26 20
             22 11 13  6  5
              2  0 12  3  7";
-        assert_eq!(day4_main(test_data), (4512, 1924));
+        assert_eq!(day4_main(test_data).unwrap(), (4512, 1924));
+
+        let game = Day4::parse(test_data).unwrap();
+        let wins = game.play();
+        assert_eq!(wins.iter().map(|win| (win.board, win.draw)).collect::<Vec<_>>(), vec![(2, 11), (0, 13), (1, 14)]);
+        // the third board's diagonal 4, 9, 23, 11, 2 is complete on the 8th draw
+        let wins = game.with_patterns(&[WinPattern::Diagonal, WinPattern::FourCorners]).play();
+        assert_eq!(wins.iter().map(|win| (win.board, win.draw)).collect::<Vec<_>>(), vec![(2, 7), (1, 19), (0, 23)]);
+
+        // boards of any size, with numbers past u8
+        let game = Day4::parse("1000,2,3\n\n1000 2\n5 3\n7 3").unwrap()
+            .with_patterns(&[WinPattern::Blackout]);
+        assert_eq!(game.play(), vec![]);
+        let game = Day4::parse("1000,2,3\n\n1000 2\n5 3").unwrap();
+        assert_eq!(game.play(), vec![Win { board: 0, draw: 1, score: 16 }]);
+        assert_eq!(game.play_indexed(), game.play());
+
+        // repeated numbers on a board and in the draws are marked alike
+        let mut rng = StdRng::seed_from_u64(4);
+        let all_patterns = [WinPattern::Row, WinPattern::Column, WinPattern::Diagonal, WinPattern::FourCorners];
+        for _ in 0..50 {
+            let (height, width) = (rng.gen_range(1..=5), rng.gen_range(1..=5));
+            let mut game = BingoGame::random(&mut rng, 20, height, width, 30);
+            for board in game.boards.iter_mut() {
+                for x in board.numbers.iter_mut() { *x %= 20; }
+            }
+            game.nums.extend(game.nums.clone());
+            let patterns = all_patterns.choose_multiple(&mut rng, 2).copied().collect::<Vec<_>>();
+            let game = game.with_patterns(&patterns);
+            assert_eq!(game.play_indexed(), game.play());
+        }
+        let err = Day4::parse("1\n\n1 2\n3").unwrap_err();
+        assert_eq!(err.location(), Some(crate::Location { line: 4, col: None }));
+
+        // boards without any cells, which have no corners
+        assert!(matches!(BingoBoard::new(Grid::new(0, 0, 0)), Err(ParseGridError::EmptyGrid)));
+        assert!(matches!(BingoBoard::new(Grid::new(3, 0, 0)), Err(ParseGridError::EmptyGrid)));
+        let err = Day4::parse("1\n\n  \n").unwrap_err();
+        assert_eq!(err.location(), Some(crate::Location { line: 3, col: None }));
     }
 }
\ No newline at end of file
@@ -1,16 +1,43 @@
-use std::process;
+use std::fmt;
+use std::error::Error;
+use std::num::ParseIntError;
+use std::collections::HashMap;
+use rand::Rng;
+use crate::grid::Grid;
+use crate::{Solution, PuzzleError, column_of};
 
-const FLOOR_SIZE: usize = 1000;
+#[derive(Debug)]
+pub enum ParseVentError {
+    InvalidCoordinate(ParseIntError), InvalidCoordinateCount(usize), InvalidPointCount(usize)
+}
+impl fmt::Display for ParseVentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseVentError::InvalidCoordinate(_) => write!(f, "invalid coordinate, expected an i32"),
+            ParseVentError::InvalidCoordinateCount(n) => write!(f, "expected 2 coordinates for a point, found {}", n),
+            ParseVentError::InvalidPointCount(n) => write!(f, "expected 2 points separated by \" -> \", found {}", n),
+        }
+    }
+}
+impl Error for ParseVentError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseVentError::InvalidCoordinate(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-struct Point(i16, i16);
+// coordinates are parsed as i32's but stored as i64's, so differences between them can't overflow
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+struct Point(i64, i64);
 impl Point {
-    fn from_str(s: &str) -> Result<Self, ()> {
+    fn from_str(s: &str) -> Result<Self, ParseVentError> {
         let coordinates = s.split(',')
-            .map(|slice| slice.trim().parse::<i16>().map_err(|_| ()))
-            .collect::<Result<Vec<_>, ()>>()?;
-        if coordinates.len() != 2 { return Err(()); }
-        Ok(Point(coordinates[0], coordinates[1]))
+            .map(|slice| slice.trim().parse::<i32>().map_err(ParseVentError::InvalidCoordinate))
+            .collect::<Result<Vec<_>, _>>()?;
+        if coordinates.len() != 2 { return Err(ParseVentError::InvalidCoordinateCount(coordinates.len())); }
+        Ok(Point(coordinates[0].into(), coordinates[1].into()))
     }
     fn flip(&mut self) {
         std::mem::swap(&mut self.0, &mut self.1);
@@ -67,112 +94,319 @@ fn rasterize_line_seg(mut start: Point, mut end: Point) -> Vec<Point> {
     points
 }
 
+// floors with at most this many points are counted in a grid, and larger ones in a hash map of the covered points
+const MAX_DENSE_POINTS: i64 = 1 << 22;
+
+// the number of lines over each point of the floor
 #[derive(Debug)]
-struct FloorMap(Box<[[u8; FLOOR_SIZE]; FLOOR_SIZE]>);
+enum FloorMap {
+    Dense { counts: Grid<u32>, origin: Point },
+    Sparse(HashMap<Point, u32>),
+}
 impl FloorMap {
-    fn new() -> Self {
-        FloorMap(Box::new([[0; FLOOR_SIZE]; FLOOR_SIZE]))
+    // a grid just large enough to contain all the line segments if it is small enough, and a sparse map otherwise
+    fn new(segments: &LineSegments) -> Self {
+        let points = segments.0.iter().flat_map(|&(start, end)| [start, end]);
+        let (min, max) = match points.clone().next() {
+            Some(first) => points.fold((first, first), |(min, max), p| {
+                (Point(min.0.min(p.0), min.1.min(p.1)), Point(max.0.max(p.0), max.1.max(p.1)))
+            }),
+            None => return FloorMap::Dense { counts: Grid::new(0, 0, 0), origin: Point(0, 0) },
+        };
+        let (width, height) = (max.0 - min.0 + 1, max.1 - min.1 + 1);
+        // the area can overflow for coordinates at both ends of the i32 range
+        if width.checked_mul(height).is_none_or(|area| area > MAX_DENSE_POINTS) {
+            return FloorMap::Sparse(HashMap::new());
+        }
+        FloorMap::Dense { counts: Grid::new(height as usize, width as usize, 0), origin: min }
     }
 
-    fn add_line_part1(&mut self, start: Point, end: Point) -> bool {
-        // horizontal line
-        if start.1 == end.1 {
-            let x_iter = match end.0 > start.0 {
-                true => start.0..=end.0,
-                false => end.0..=start.0
-            };
-            for x in x_iter {
-                self.0[start.1 as usize][x as usize] += 1;
-            }
-            return true;
+    fn add_point(&mut self, p: Point) {
+        match self {
+            FloorMap::Dense { counts, origin } => counts[((p.1 - origin.1) as usize, (p.0 - origin.0) as usize)] += 1,
+            FloorMap::Sparse(counts) => *counts.entry(p).or_default() += 1,
         }
-        // vertical line
-        if start.0 == end.0 {
-            let y_iter = match end.1 > start.1 {
-                true => start.1..=end.1,
-                false => end.1..=start.1
-            };
-            for y in y_iter {
-                self.0[y as usize][start.0 as usize] += 1;
-            }
-            return true;
-        }
-        false
+    }
+
+    fn add_line_part1(&mut self, start: Point, end: Point) -> bool {
+        // only horizontal and vertical lines
+        if start.0 != end.0 && start.1 != end.1 { return false; }
+        self.add_line_part2(start, end)
     }
 
     fn add_line_part2(&mut self, start: Point, end: Point) -> bool {
-        // let line_coordinates = match LineCoordIterator::new(start, end) {
-        //     Err(_) => return false,
-        //     Ok(x) => x
-        // };
+        // only horizontal, vertical and 45 degree lines
+        let delta = end - start;
+        if delta.0 != 0 && delta.1 != 0 && delta.0.abs() != delta.1.abs() { return false; }
         let line_coordinates = rasterize_line_seg(start, end);
-        for Point(x, y) in line_coordinates {
-            self.0[y as usize][x as usize] += 1;
+        for p in line_coordinates {
+            self.add_point(p);
         }
         true
     }
 
-    fn count_intersections(&self) -> u16 {
-        self.0.iter().flatten().fold(0, |acc, x| {
-            if *x > 1 { return acc + 1; }
-            acc
-        })
+    fn count_intersections(&self) -> usize {
+        match self {
+            FloorMap::Dense { counts, .. } => counts.iter().filter(|&&x| x > 1).count(),
+            FloorMap::Sparse(counts) => counts.values().filter(|&&x| x > 1).count(),
+        }
     }
 }
 
-pub fn day5_main(file_data: &str) -> (u16, u16) {
-    // Part 1
-    let mut floor_map = FloorMap::new();
-    for (i, line) in file_data.lines().enumerate() {
-        let coordinates = line.split(" -> ")
-            .map(Point::from_str)
-            .collect::<Result<Vec<Point>, ()>>()
-            .unwrap_or_else(|_| {
-                eprintln!("Error parsing coordinates on line {}!", i+1);
-                process::exit(1);
-            });
-        if coordinates.len() != 2 {
-            eprintln!("Invalid number of tokens in line {}!", i+1);
-            process::exit(1);
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+fn cross(a: Point, b: Point) -> i128 {
+    a.0 as i128 * b.1 as i128 - a.1 as i128 * b.0 as i128
+}
+
+// a line segment as the lattice points `start + k*step` for `k` in `0..=steps`, which are exactly the points with
+// integer coordinates on the segment. `step` is primitive and points right (or up if vertical), so collinear
+// segments always share the same step, and a single point is given the step of a horizontal line
+#[derive(Debug, Copy, Clone)]
+struct LatticeSegment {
+    start: Point,
+    step: Point,
+    steps: i64,
+}
+impl LatticeSegment {
+    fn new(start: Point, end: Point) -> Self {
+        let delta = end - start;
+        let steps = gcd(delta.0, delta.1);
+        if steps == 0 { return LatticeSegment { start, step: Point(1, 0), steps }; }
+        let step = Point(delta.0 / steps, delta.1 / steps);
+        if step.0 < 0 || (step.0 == 0 && step.1 < 0) {
+            LatticeSegment { start: end, step: Point(-step.0, -step.1), steps }
+        } else {
+            LatticeSegment { start, step, steps }
         }
-        if !floor_map.add_line_part1(coordinates[0], coordinates[1]) {
-            println!("[Part 1] The vent coordinates on line {} was ignored!", i+1);
+    }
+
+    fn end(&self) -> Point {
+        Point(self.start.0 + self.step.0*self.steps, self.start.1 + self.step.1*self.steps)
+    }
+
+    fn line(&self) -> Line {
+        (self.step, cross(self.step, self.start))
+    }
+
+    // the distance between consecutive lattice points of the line, measured by `position`
+    fn spacing(&self) -> i64 {
+        if self.step.0 != 0 { self.step.0 } else { self.step.1 }
+    }
+
+    // orders the points of the line, which are all congruent modulo `spacing`
+    fn position(&self, p: Point) -> i64 {
+        if self.step.0 != 0 { p.0 } else { p.1 }
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        if cross(self.step, p - self.start) != 0 { return false; }
+        let k = (self.position(p) - self.position(self.start)) / self.spacing();
+        (0..=self.steps).contains(&k)
+    }
+
+    // the lattice point where two segments which aren't parallel cross, if there is one
+    fn crossing(&self, other: &LatticeSegment) -> Option<Point> {
+        if self.step == other.step { return None; }
+        if self.steps == 0 { return other.contains(self.start).then_some(self.start); }
+        if other.steps == 0 { return self.contains(other.start).then_some(other.start); }
+
+        // solve `self.start + t*self.step == other.start + u*other.step` for integers t and u
+        let denominator = cross(self.step, other.step);
+        let offset = other.start - self.start;
+        let (t, u) = (cross(offset, other.step), cross(offset, self.step));
+        if t % denominator != 0 || u % denominator != 0 { return None; }
+        let (t, u) = (t / denominator, u / denominator);
+        if !(0..=self.steps as i128).contains(&t) || !(0..=other.steps as i128).contains(&u) { return None; }
+        let t = t as i64;
+        Some(Point(self.start.0 + t*self.step.0, self.start.1 + t*self.step.1))
+    }
+}
+
+// identifies a line by its step and the cross product of the step with any point on it
+type Line = (Point, i128);
+
+// the inclusive ranges of positions covered by at least two segments on a line
+type Overlaps = Vec<(i64, i64)>;
+
+// sweeps along a line, finding where the segments on it overlap
+fn collinear_overlaps(segments: &[LatticeSegment]) -> Overlaps {
+    let spacing = segments[0].spacing();
+    let mut events = segments.iter()
+        .flat_map(|seg| [(seg.position(seg.start), 1), (seg.position(seg.end()) + spacing, -1)])
+        .collect::<Vec<(i64, i32)>>();
+    events.sort_unstable();
+
+    let mut overlaps = Overlaps::new();
+    let mut covering = 0;
+    for (position, change) in events {
+        let was_overlapping = covering > 1;
+        covering += change;
+        match (was_overlapping, covering > 1) {
+            (false, true) => overlaps.push((position, position)),
+            (true, false) => overlaps.last_mut().unwrap().1 = position - spacing,
+            _ => (),
         }
     }
-    let intersection_count_1 = floor_map.count_intersections();
-    println!("There are {} points where the lines intersect!", intersection_count_1);
+    // ranges which touch were split by an end and a start at the same position
+    overlaps.dedup_by(|next, prev| {
+        let touching = next.0 == prev.1 + spacing;
+        if touching { prev.1 = next.1; }
+        touching
+    });
+    overlaps
+}
 
+fn overlaps_contain(overlaps: &Overlaps, position: i64) -> bool {
+    let i = overlaps.partition_point(|&(_, end)| end < position);
+    overlaps.get(i).is_some_and(|&(start, _)| start <= position)
+}
 
-    // Part 2
-    let mut floor_map = FloorMap::new();
-    for (i, line) in file_data.lines().enumerate() {
-        let coordinates = line.split(" -> ")
-            .map(Point::from_str)
-            .collect::<Result<Vec<Point>, ()>>()
-            .unwrap_or_else(|_| {
-                eprintln!("Error parsing coordinates on line {}!", i+1);
-                process::exit(1);
-            });
-        if coordinates.len() != 2 {
-            eprintln!("Invalid number of tokens in line {}!", i+1);
-            process::exit(1);
+/// Counts the points with integer coordinates covered by at least two of the line segments, without visiting the
+/// points along them. Segments may lie at any angle, in which case only the points with integer coordinates
+/// exactly on them are covered (part 2 rejects such segments instead). With `include_diagonals` false, only
+/// horizontal and vertical segments are counted as in part 1.
+pub fn count_overlaps_sweep(segments: &LineSegments, include_diagonals: bool) -> usize {
+    let mut segments = segments.0.iter()
+        .filter(|(start, end)| include_diagonals || start.0 == end.0 || start.1 == end.1)
+        .map(|&(start, end)| LatticeSegment::new(start, end))
+        .collect::<Vec<_>>();
+
+    // the points where collinear segments overlap
+    let mut lines: HashMap<_, Vec<LatticeSegment>> = HashMap::new();
+    for &seg in segments.iter() {
+        lines.entry(seg.line()).or_default().push(seg);
+    }
+    let lines = lines.into_iter()
+        .map(|(line, segments)| (line, (segments[0].spacing(), collinear_overlaps(&segments))))
+        .filter(|(_, (_, overlaps))| !overlaps.is_empty())
+        .collect::<HashMap<_, _>>();
+
+    // sweep from left to right, only testing pairs of segments which share an x coordinate for crossings. A
+    // crossing point may also be on the overlaps of one or more lines, in which case some segment on each of those
+    // lines crosses there too, so every such line is found
+    segments.sort_unstable_by_key(|seg| seg.start.0);
+    let mut crossings: HashMap<Point, Vec<Line>> = HashMap::new();
+    let mut active: Vec<LatticeSegment> = Vec::new();
+    for seg in segments {
+        active.retain(|other| other.end().0 >= seg.start.0);
+        for other in active.iter() {
+            if let Some(p) = seg.crossing(other) {
+                let overlapping_lines = crossings.entry(p).or_default();
+                for s in [&seg, other] {
+                    let on_overlap = lines.get(&s.line())
+                        .is_some_and(|(_, overlaps)| overlaps_contain(overlaps, s.position(p)));
+                    if on_overlap && !overlapping_lines.contains(&s.line()) { overlapping_lines.push(s.line()); }
+                }
+            }
         }
-        if !floor_map.add_line_part2(coordinates[0], coordinates[1]) {
-            eprintln!("Unable to map the vent coordinates on line {}!", i+1);
-            eprintln!("The coordinates must form a horizontal, vertical or 45 degree line.");
-            process::exit(1);
+        active.push(seg);
+    }
+
+    // points on the overlaps of several lines were counted once for each
+    let collinear_count: usize = lines.values()
+        .flat_map(|(spacing, overlaps)| {
+            overlaps.iter().map(move |&(start, end)| ((end - start) / spacing + 1) as usize)
+        })
+        .sum();
+    let recounted: usize = crossings.values().map(|lines| lines.len().saturating_sub(1)).sum();
+    let crossing_count = crossings.values().filter(|lines| lines.is_empty()).count();
+    collinear_count - recounted + crossing_count
+}
+
+/// The line segments of the hydrothermal vents.
+pub struct LineSegments(Vec<(Point, Point)>);
+impl LineSegments {
+    /// Generates `count` random horizontal, vertical and diagonal segments with coordinates in `0..size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not positive.
+    pub fn random<R: Rng>(rng: &mut R, count: usize, size: i32) -> Self {
+        let segments = (0..count)
+            .map(|_| {
+                let start = Point(rng.gen_range(0..size).into(), rng.gen_range(0..size).into());
+                let end = Point(rng.gen_range(0..size).into(), rng.gen_range(0..size).into());
+                let end = match rng.gen_range(0..3) {
+                    0 => Point(end.0, start.1),
+                    1 => Point(start.0, end.1),
+                    _ => {
+                        let delta = end - start;
+                        let len = delta.0.abs().min(delta.1.abs());
+                        Point(start.0 + len*delta.0.signum(), start.1 + len*delta.1.signum())
+                    }
+                };
+                (start, end)
+            })
+            .collect();
+        LineSegments(segments)
+    }
+}
+
+pub struct Day5;
+impl Solution for Day5 {
+    type Input = LineSegments;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        let segments = file_data.lines().enumerate()
+            .map(|(i, line)| {
+                let coordinates = line.split(" -> ")
+                    .map(|s| Point::from_str(s).map_err(|e| PuzzleError::at(i+1, column_of(line, s), e)))
+                    .collect::<Result<Vec<Point>, _>>()?;
+                if coordinates.len() != 2 {
+                    return Err(PuzzleError::at_line(i+1, ParseVentError::InvalidPointCount(coordinates.len())));
+                }
+                Ok((coordinates[0], coordinates[1]))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(LineSegments(segments))
+    }
+
+    fn part1(segments: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        let mut floor_map = FloorMap::new(segments);
+        for &(start, end) in segments.0.iter() {
+            // lines which are not horizontal or vertical are ignored
+            floor_map.add_line_part1(start, end);
+        }
+        Ok(floor_map.count_intersections())
+    }
+
+    fn part2(segments: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        let mut floor_map = FloorMap::new(segments);
+        for (i, &(start, end)) in segments.0.iter().enumerate() {
+            if !floor_map.add_line_part2(start, end) {
+                return Err(PuzzleError::at_line(i+1,
+                    "the coordinates must form a horizontal, vertical or 45 degree line"));
+            }
         }
+        Ok(floor_map.count_intersections())
     }
-    let intersection_count_2 = floor_map.count_intersections();
+}
+
+pub fn day5_main(file_data: &str) -> Result<(usize, usize), PuzzleError> {
+    let segments = Day5::parse(file_data)?;
+
+    // Part 1
+    let intersection_count_1 = Day5::part1(&segments)?;
+    println!("There are {} points where the lines intersect!", intersection_count_1);
+
+    // Part 2
+    let intersection_count_2 = Day5::part2(&segments)?;
     println!("There are {} points where the lines intersect!", intersection_count_2);
 
-    (intersection_count_1, intersection_count_2)
+    Ok((intersection_count_1, intersection_count_2))
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
 
     #[test]
     fn it_works() {
@@ -187,6 +421,66 @@ mod tests {
             3,4 -> 1,4
             0,0 -> 8,8
             5,5 -> 8,2";
-        assert_eq!(day5_main(test_data), (5, 12)); // part 2
+        assert_eq!(day5_main(test_data).unwrap(), (5, 12)); // part 2
+        let segments = Day5::parse(test_data).unwrap();
+        assert_eq!(count_overlaps_sweep(&segments, false), 5);
+        assert_eq!(count_overlaps_sweep(&segments, true), 12);
+
+        // a floor that is only one column wide
+        let segments = Day5::parse("0,0 -> 0,2\n0,1 -> 0,3").unwrap();
+        assert_eq!(Day5::part1(&segments).unwrap(), 2);
+
+        // negative and far apart coordinates, which are counted sparsely
+        let segments = Day5::parse("-5,-5 -> 5,5\n-5,5 -> 5,-5\n100000,7 -> -100000,7\n7,-100 -> 7,100").unwrap();
+        assert!(matches!(FloorMap::new(&segments), FloorMap::Sparse(_)));
+        assert_eq!(Day5::part1(&segments).unwrap(), 1);
+        assert_eq!(Day5::part2(&segments).unwrap(), 2);
+        let segments = Day5::parse("-5,-5 -> 5,5\n-5,5 -> 5,-5\n-3,-3 -> -3,3").unwrap();
+        assert!(matches!(FloorMap::new(&segments), FloorMap::Dense { .. }));
+        assert_eq!(Day5::part2(&segments).unwrap(), 3);
+        let segments = Day5::parse("-2147483648,-2147483648 -> -2147483648,-2147483648
+            2147483647,2147483647 -> 2147483647,2147483647").unwrap();
+        assert!(matches!(FloorMap::new(&segments), FloorMap::Sparse(_)));
+        assert_eq!(Day5::part2(&segments).unwrap(), 0);
+        assert_eq!(count_overlaps_sweep(&segments, true), 0);
+
+        // the sweep counts lines at any angle and very long lines without visiting their points
+        let segments = Day5::parse("0,0 -> 6,3\n8,4 -> 2,1\n4,0 -> 4,5\n0,2 -> 8,2\n1,5 -> 7,-1\n3,3 -> 3,3\n0,6 -> 6,0");
+        let segments = segments.unwrap();
+        assert_eq!(count_overlaps_sweep(&segments, true), 8);
+        let segments = Day5::parse("0,0 -> 6,3\n2,1 -> 2,1\n2,1 -> 5,1").unwrap();
+        assert_eq!(count_overlaps_sweep(&segments, true), 1);
+
+        // part 2 agrees with the sweep on 45 degree lines, but rejects lines at any other angle
+        let segments = Day5::parse("0,0 -> 4,4\n4,0 -> 0,4\n2,0 -> 2,4\n0,2 -> 4,2\n1,1 -> 3,3").unwrap();
+        assert_eq!(Day5::part2(&segments).unwrap(), 3);
+        assert_eq!(count_overlaps_sweep(&segments, true), 3);
+        let segments = Day5::parse("0,0 -> 4,4\n0,0 -> 6,3\n2,0 -> 2,4").unwrap();
+        let err = Day5::part2(&segments).unwrap_err();
+        assert_eq!(err.location(), Some(crate::Location { line: 2, col: None }));
+        assert_eq!(Day5::part1(&segments).unwrap(), 0);
+        assert_eq!(count_overlaps_sweep(&segments, true), 3);
+        let segments = Day5::parse("-2000000000,0 -> 2000000000,0\n0,-2000000000 -> 0,2000000000\n0,0 -> -2000000000,0")
+            .unwrap();
+        assert_eq!(count_overlaps_sweep(&segments, false), 2000000001);
+
+        let mut rng = StdRng::seed_from_u64(2021);
+        for _ in 0..20 {
+            let segments = LineSegments::random(&mut rng, 50, 30);
+            assert_eq!(count_overlaps_sweep(&segments, false), Day5::part1(&segments).unwrap());
+            assert_eq!(count_overlaps_sweep(&segments, true), Day5::part2(&segments).unwrap());
+
+            // at any angle, against the lattice points of each segment
+            let mut random_point = || Point(rng.gen_range(-8..8), rng.gen_range(-8..8));
+            let segments = LineSegments((0..30).map(|_| (random_point(), random_point())).collect());
+            let mut counts: HashMap<Point, u32> = HashMap::new();
+            for &(start, end) in segments.0.iter() {
+                let seg = LatticeSegment::new(start, end);
+                for k in 0..=seg.steps {
+                    *counts.entry(Point(seg.start.0 + k*seg.step.0, seg.start.1 + k*seg.step.1)).or_default() += 1;
+                }
+            }
+            assert_eq!(count_overlaps_sweep(&segments, true), counts.values().filter(|&&x| x > 1).count());
+        }
     }
 }
\ No newline at end of file
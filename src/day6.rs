@@ -1,5 +1,30 @@
-use std::process;
-use std::collections::HashMap;
+use std::fmt;
+use std::error::Error;
+use std::num::ParseIntError;
+use crate::{Solution, PuzzleError, column_of};
+use crate::bigint::BigUint;
+use std::array;
+
+#[derive(Debug)]
+pub enum ParseAgeError {
+    InvalidAge(ParseIntError), OutOfRange(u8)
+}
+impl fmt::Display for ParseAgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseAgeError::InvalidAge(_) => write!(f, "invalid lanternfish age"),
+            ParseAgeError::OutOfRange(age) => write!(f, "the age must be within 0..=8, found {}", age),
+        }
+    }
+}
+impl Error for ParseAgeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseAgeError::InvalidAge(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 type LanternfishAge = u8;
 fn next_day_part_1(all_fish: &mut Vec<LanternfishAge>) {
@@ -15,41 +40,248 @@ fn next_day_part_1(all_fish: &mut Vec<LanternfishAge>) {
     for _ in 0..new_count { all_fish.push(8); }
 }
 
-struct LanternfishCollection(HashMap<LanternfishAge, u64>);
-impl LanternfishCollection {
-    fn new() -> Self {
-        let mut ret_val = LanternfishCollection(HashMap::with_capacity(9));
-        for i in 0..=8 { ret_val.0.insert(i, 0); }
-        ret_val
+/// The largest number of life stages a lanternfish may go through, which bounds the array backing a `Population`.
+pub const MAX_LIFE_STAGES: usize = 256;
+
+#[derive(Debug)]
+pub enum LifeCycleError {
+    TooManyStages(usize), TimerOutOfRange { timer: LanternfishAge, min: usize, max: usize }
+}
+impl fmt::Display for LifeCycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LifeCycleError::TooManyStages(n) =>
+                write!(f, "the life cycle has {} stages, but at most {} are supported", n, MAX_LIFE_STAGES),
+            LifeCycleError::TimerOutOfRange { timer, min, max } =>
+                write!(f, "the timer must be within {}..={} for this life cycle, found {}", min, max, timer),
+        }
+    }
+}
+impl Error for LifeCycleError {}
+
+/// How lanternfish reproduce. A fish whose timer is 0 spawns a newborn and restarts its timer the next day.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct LifeCycle {
+    /// The timer a fish restarts from after spawning.
+    pub reset_timer: u8,
+    /// The timer a newborn starts from.
+    pub newborn_timer: u8,
+    /// The days a newborn waits before its timer starts.
+    pub maturity_delay: u8,
+    /// The age in days at which a fish dies, if it does.
+    pub death_age: Option<usize>,
+}
+impl Default for LifeCycle {
+    // the life cycle from the puzzle
+    fn default() -> Self {
+        LifeCycle { reset_timer: 6, newborn_timer: 8, maturity_delay: 0, death_age: None }
+    }
+}
+impl LifeCycle {
+    // the age at which a fish first spawns
+    fn first_spawn(&self) -> usize {
+        self.newborn_timer as usize + self.maturity_delay as usize + 1
     }
 
-    fn next_day(&mut self) {
-        let temp = *self.0.get(&0).unwrap();
-        for key in 0..=7 {
-            *self.0.get_mut(&key).unwrap() = *self.0.get(&(key+1)).unwrap();
+    // the days between spawns
+    fn period(&self) -> usize {
+        self.reset_timer as usize + 1
+    }
+
+    // fish are tracked by age. Fish which never die are alike once they have spawned once, so their ages wrap around
+    // to the first spawn after a period
+    fn stages(&self) -> usize {
+        self.death_age.unwrap_or(self.first_spawn() + self.period())
+    }
+
+    fn spawns_at(&self, age: usize) -> bool {
+        age >= self.first_spawn() && (age - self.first_spawn()).is_multiple_of(self.period())
+    }
+}
+
+/// The number of lanternfish at each age under a life cycle.
+#[derive(Debug, Clone)]
+pub struct Population {
+    life_cycle: LifeCycle,
+    counts: [u64; MAX_LIFE_STAGES],
+}
+impl Population {
+    /// Starts from fish with the given timers, which are taken to have been born with the newborn timer (so a fish
+    /// with timer `t` is `newborn_timer + maturity_delay - t` days old).
+    pub fn new(life_cycle: LifeCycle, all_fish: &[LanternfishAge]) -> Result<Self, LifeCycleError> {
+        let stages = life_cycle.stages();
+        if stages > MAX_LIFE_STAGES { return Err(LifeCycleError::TooManyStages(stages)); }
+        let mut counts = [0; MAX_LIFE_STAGES];
+        let max = life_cycle.first_spawn() - 1;
+        let min = (max + 1).saturating_sub(stages);
+        for &timer in all_fish {
+            if !(min..=max).contains(&(timer as usize)) {
+                return Err(LifeCycleError::TimerOutOfRange { timer, min, max });
+            }
+            counts[max - timer as usize] += 1;
         }
-        *self.0.get_mut(&8).unwrap() = temp;
-        *self.0.get_mut(&6).unwrap() += temp;
+        Ok(Population { life_cycle, counts })
     }
 
-    fn fish_count(&self) -> u64 {
-        self.0.values().fold(0, |acc, count| acc + *count)
+    pub fn next_day(&mut self) {
+        let stages = self.life_cycle.stages();
+        let mut counts = [0; MAX_LIFE_STAGES];
+        let mut newborns = 0;
+        for (age, &count) in self.counts[..stages].iter().enumerate() {
+            let next_age = match self.life_cycle.death_age {
+                Some(_) if age + 1 == stages => continue,   // dies
+                None if age + 1 == stages => self.life_cycle.first_spawn(),
+                _ => age + 1,
+            };
+            if self.life_cycle.spawns_at(next_age) { newborns += count; }
+            counts[next_age] += count;
+        }
+        counts[0] += newborns;
+        self.counts = counts;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The population today and after each of the next `days` days, leaving it at the last of them.
+    pub fn time_series(&mut self, days: usize) -> PopulationSeries {
+        let mut counts = Vec::with_capacity(days + 1);
+        counts.push(self.count());
+        for _ in 0..days {
+            self.next_day();
+            counts.push(self.count());
+        }
+        PopulationSeries(counts)
     }
 }
 
-pub fn day6_main(file_data: &str) -> (usize, Vec<LanternfishAge>, u64) {
+/// The population on consecutive days.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PopulationSeries(Vec<u64>);
+impl PopulationSeries {
+    pub fn counts(&self) -> &[u64] {
+        &self.0
+    }
+
+    /// One row per day with its number and population.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("day,population\n");
+        for (day, count) in self.0.iter().enumerate() {
+            csv.push_str(&format!("{},{}\n", day, count));
+        }
+        csv
+    }
+}
+
+// the arithmetic which lanternfish are counted with
+trait Counting {
+    type Count: Clone;
+    fn count(&self, n: u64) -> Self::Count;
+    fn add(&self, a: &Self::Count, b: &Self::Count) -> Self::Count;
+    fn mul(&self, a: &Self::Count, b: &Self::Count) -> Self::Count;
+}
+
+struct Exact;
+impl Counting for Exact {
+    type Count = BigUint;
+    fn count(&self, n: u64) -> BigUint { BigUint::from(n) }
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint { a + b }
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint { a * b }
+}
+
+struct Modulo(u64);
+impl Counting for Modulo {
+    type Count = u64;
+    fn count(&self, n: u64) -> u64 { n % self.0 }
+    fn add(&self, a: &u64, b: &u64) -> u64 { ((*a as u128 + *b as u128) % self.0 as u128) as u64 }
+    fn mul(&self, a: &u64, b: &u64) -> u64 { ((*a as u128 * *b as u128) % self.0 as u128) as u64 }
+}
+
+const AGE_COUNT: usize = 9;
+type Matrix<T> = [[T; AGE_COUNT]; AGE_COUNT];
+
+fn multiply<C: Counting>(counting: &C, a: &Matrix<C::Count>, b: &Matrix<C::Count>) -> Matrix<C::Count> {
+    array::from_fn(|i| array::from_fn(|j| {
+        (0..AGE_COUNT).fold(counting.count(0), |acc, k| counting.add(&acc, &counting.mul(&a[i][k], &b[k][j])))
+    }))
+}
+
+// raises the transition matrix to the power of `days` by repeated squaring, applying it to the counts of each age
+fn population_after_days<C: Counting>(counting: &C, all_fish: &[LanternfishAge], mut days: u64) -> C::Count {
+    // only the first row is used, as the counts of each age
+    let mut counts: Matrix<C::Count> = array::from_fn(|row| array::from_fn(|age| {
+        let count = all_fish.iter().filter(|&&fish| fish as usize == age).count();
+        counting.count(if row == 0 { count as u64 } else { 0 })
+    }));
+    // the number of fish of age `to` which a fish of age `from` becomes after a day
+    let mut transition: Matrix<C::Count> = array::from_fn(|from| array::from_fn(|to| {
+        let becomes = if from == 0 { to == 6 || to == 8 } else { to == from - 1 };
+        counting.count(becomes as u64)
+    }));
+
+    while days > 0 {
+        if days & 1 == 1 { counts = multiply(counting, &counts, &transition); }
+        transition = multiply(counting, &transition, &transition);
+        days >>= 1;
+    }
+    counts[0].iter().fold(counting.count(0), |acc, count| counting.add(&acc, count))
+}
+
+/// The number of lanternfish after `days` days, using O(log `days`) multiplications of the 9x9 matrix which advances
+/// the counts of each age by a day. The population grows exponentially, so the number of digits in the answer (and
+/// the time to multiply them) still grows linearly with `days`.
+pub fn population_after(all_fish: &[LanternfishAge], days: u64) -> BigUint {
+    population_after_days(&Exact, all_fish, days)
+}
+
+/// The number of lanternfish after `days` days modulo `modulus` (usually a large prime), in O(log `days`) time.
+///
+/// # Panics
+///
+/// Panics if `modulus` is 0.
+pub fn population_after_mod(all_fish: &[LanternfishAge], days: u64, modulus: u64) -> u64 {
+    assert!(modulus > 0, "the modulus must be positive");
+    population_after_days(&Modulo(modulus), all_fish, days)
+}
+
+pub struct Day6;
+impl Solution for Day6 {
+    type Input = Vec<LanternfishAge>;
+    type Answer1 = usize;
+    type Answer2 = u64;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        let line = file_data.trim_end();
+        line.split(',')
+            .map(|c| {
+                let num = c.trim().parse::<LanternfishAge>()
+                    .map_err(|e| PuzzleError::at(1, column_of(line, c), ParseAgeError::InvalidAge(e)))?;
+                if num > 8 { return Err(PuzzleError::at(1, column_of(line, c), ParseAgeError::OutOfRange(num))); }
+                Ok(num)
+            })
+            .collect()
+    }
+
+    fn part1(all_fish: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        let mut all_fish = all_fish.clone();
+        for _ in 1..=80 { next_day_part_1(&mut all_fish); }
+        Ok(all_fish.len())
+    }
+
+    fn part2(all_fish: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        let mut all_fish = Population::new(LifeCycle::default(), all_fish)
+            .map_err(PuzzleError::invalid)?;
+        for _ in 1..=256 { all_fish.next_day(); }
+        Ok(all_fish.count())
+    }
+}
+
+pub fn day6_main(file_data: &str) -> Result<(usize, Vec<LanternfishAge>, u64), PuzzleError> {
+    let initial_lanternfish = Day6::parse(file_data)?;
+
     // Part 1
-    let mut all_lanternfish = file_data
-        .split(',').enumerate()
-        .map(|(i, c)| {
-            c.parse::<LanternfishAge>().map_err(|e| (i, e))
-        })
-        .collect::<Result<Vec<_>, _>>()
-        .unwrap_or_else(|(i, e)| {
-            eprintln!("Error parsing the age of lanternfish {}! :", i);
-            eprintln!("{}", e);
-            process::exit(1);
-        });
+    let mut all_lanternfish = initial_lanternfish.clone();
     for _ in 1..=18 { next_day_part_1(&mut all_lanternfish); }
     let day_18_snapshot = all_lanternfish.clone();
     for _ in 19..=80 { next_day_part_1(&mut all_lanternfish); }
@@ -58,26 +290,10 @@ pub fn day6_main(file_data: &str) -> (usize, Vec<LanternfishAge>, u64) {
 
 
     // Part 2
-    let mut all_lanternfish = LanternfishCollection::new();
-    file_data.split(',').enumerate()
-        .map(|(i, c)| {
-            let num = c.parse::<LanternfishAge>().map_err(|_| i)?;
-            if num > 8 { return Err(i); }
-            Ok(num)
-        })
-        .for_each(|x| {
-            let age = x.unwrap_or_else(|i| {
-                eprintln!("Error parsing the age of lanternfish {}! :", i);
-                eprintln!("The age must be an integer in 0..=8!");
-                process::exit(1);
-            });
-            *all_lanternfish.0.get_mut(&age).unwrap() += 1;
-        });
-    for _ in 1..=256 { all_lanternfish.next_day(); }
-    let day_256_total = all_lanternfish.fish_count();
+    let day_256_total = Day6::part2(&initial_lanternfish)?;
     println!("There will be {} lanternfish after 256 days.", day_256_total);
 
-    (day_80_total, day_18_snapshot, day_256_total)
+    Ok((day_80_total, day_18_snapshot, day_256_total))
 }
 
 
@@ -92,10 +308,42 @@ mod tests {
             .split(',')
             .map(|c| c.parse::<LanternfishAge>().unwrap())
             .collect::<Vec<_>>();
-        let (day_80_total, day_18_snapshot, day_256_total) = day6_main(test_data);
+        let (day_80_total, day_18_snapshot, day_256_total) = day6_main(test_data).unwrap();
         assert_eq!(day_18_snapshot, string_of_day_18_lanternfish, "Not the same string of lanterfish on day 18. :(");
         assert_eq!(day_80_total, 5934, "Number of lanternfish on day 80 incorrect!");
         assert_eq!(day_256_total, 26984457539, "Number of lanternfish on day 256 incorrect!");
+
+        let all_fish = Day6::parse(test_data).unwrap();
+        assert_eq!(population_after(&all_fish, 256).to_string(), "26984457539");
+        assert_eq!(population_after(&all_fish, 0).to_string(), "5");
+        assert_eq!(population_after(&all_fish, 1000).to_string(), "379589061144698259131825683795505058481");
+        assert_eq!(population_after_mod(&all_fish, 256, 1_000_000_007), 26984457539 % 1_000_000_007);
+        assert_eq!(population_after_mod(&all_fish, 1_000_000_000_000_000_000, 1_000_000_007), 860170227);
+        assert_eq!(population_after_mod(&all_fish, 1_000_000_000_000_000_000, 998_244_353), 90370603);
+
+        // life cycles, compared with simulating each fish
+        let mut population = Population::new(LifeCycle::default(), &all_fish).unwrap();
+        let series = population.time_series(6);
+        assert_eq!(series.counts(), &[5, 5, 6, 7, 9, 10, 10]);
+        assert!(series.to_csv().starts_with("day,population\n0,5\n1,5\n2,6\n"));
+        for _ in 6..80 { population.next_day(); }
+        assert_eq!(population.count(), 5934);
+        let delayed = LifeCycle { maturity_delay: 2, ..LifeCycle::default() };
+        let late_newborns = LifeCycle { newborn_timer: 10, ..LifeCycle::default() };
+        assert_eq!(Population::new(delayed, &all_fish).unwrap().time_series(60).counts()[60], 631);
+        assert_eq!(Population::new(late_newborns, &all_fish).unwrap().time_series(60),
+            Population::new(delayed, &all_fish).unwrap().time_series(60));
+        let mortal = LifeCycle { maturity_delay: 1, death_age: Some(20), ..LifeCycle::default() };
+        assert_eq!(Population::new(mortal, &all_fish).unwrap().time_series(60).counts(), &[5, 5, 6, 7, 9, 10, 10, 10,
+            10, 11, 12, 14, 15, 15, 15, 15, 15, 15, 15, 17, 19, 23, 25, 25, 25, 25, 26, 27, 29, 32, 34, 38, 40, 40, 40,
+            40, 43, 46, 52, 57, 59, 63, 65, 66, 67, 69, 75, 80, 90, 97, 99, 103, 105, 109, 113, 121, 132, 139, 153,
+            162, 165]);
+        let fast = LifeCycle { reset_timer: 2, newborn_timer: 4, maturity_delay: 0, death_age: Some(10) };
+        assert_eq!(Population::new(fast, &all_fish).unwrap().time_series(30).counts()[30], 157);
+        let error = Population::new(fast, &[5]).unwrap_err();
+        assert!(matches!(error, LifeCycleError::TimerOutOfRange { timer: 5, min: 0, max: 4 }));
+        let long_lived = LifeCycle { death_age: Some(1000), ..LifeCycle::default() };
+        assert!(matches!(Population::new(long_lived, &all_fish), Err(LifeCycleError::TooManyStages(1000))));
     }
 }
 
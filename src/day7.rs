@@ -1,41 +1,78 @@
+use std::fmt;
+use std::error::Error;
 use std::collections::BTreeMap;
 use std::cmp::Ordering;
+use crate::{Solution, PuzzleError, column_of};
 
-type CrabPos = u16;
+pub type CrabPos = u64;
+pub type Fuel = u64;
+/// How much fuel a crab uses compared to a crab with weight 1.
+pub type CrabWeight = u64;
+// an alignment position and the fuel needed to reach it
+type Alignment = (CrabPos, Fuel);
 
+// the widest range of positions that `day7_main` checks against a brute force search
+const MAX_BRUTE_FORCE_RANGE: CrabPos = 10_000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FuelOverflow;
+impl fmt::Display for FuelOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the fuel needed to align the crabs overflows")
+    }
+}
+impl Error for FuelOverflow {}
+
+impl From<FuelOverflow> for PuzzleError {
+    fn from(e: FuelOverflow) -> Self {
+        PuzzleError::no_solution(e.to_string())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ZeroWeight;
+impl fmt::Display for ZeroWeight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the weight of a crab must be positive")
+    }
+}
+impl Error for ZeroWeight {}
+
+/// The fuel a crab uses to move a distance in part 1.
 #[inline]
-fn fuel_formula_p1(distance: CrabPos) -> u32 {
-    distance as u32
+pub fn fuel_formula_p1(distance: CrabPos) -> Option<Fuel> {
+    Some(distance)
 }
+/// The fuel a crab uses to move a distance in part 2, or `None` if it overflows.
 #[inline]
-fn fuel_formula_p2(distance: CrabPos) -> u32 {
-    (distance as u32 * (distance + 1) as u32) / 2
+pub fn fuel_formula_p2(distance: CrabPos) -> Option<Fuel> {
+    // halve whichever factor is even first, so the product only overflows if the result does
+    let next = distance.checked_add(1)?;
+    if distance.is_multiple_of(2) { (distance / 2).checked_mul(next) } else { distance.checked_mul(next / 2) }
 }
-fn find_ideal_alignment(all_pos: &[CrabPos], min_max_pos: (CrabPos, CrabPos),
-    fuel_calculator: fn(CrabPos) -> u32) -> Option<(CrabPos, u32)> {
-    if all_pos.is_empty() { return None; }
-    let mut min_fuel = u32::MAX;
+fn find_ideal_alignment<F: Fn(CrabPos) -> Option<Fuel>>(all_pos: &[CrabPos], min_max_pos: (CrabPos, CrabPos),
+    fuel_calculator: F) -> Result<Option<Alignment>, FuelOverflow> {
+    if all_pos.is_empty() { return Ok(None); }
+    let mut min_fuel = Fuel::MAX;
     let mut ideal_pos = 0;
 
-    for i in min_max_pos.0..min_max_pos.1 {
-        let current = all_pos.iter().fold(0, |acc, pos| {
-            let abs_distance = match *pos > i {
-                true => pos - i,
-                false => i - pos
-            };
-            acc + (fuel_calculator(abs_distance) as u32)
+    for i in min_max_pos.0..=min_max_pos.1 {
+        let current = all_pos.iter().try_fold(0 as Fuel, |acc, pos| {
+            acc.checked_add(fuel_calculator(pos.abs_diff(i))?)
         });
+        let current = current.ok_or(FuelOverflow)?;
         if current < min_fuel {
             min_fuel = current;
             ideal_pos = i;
         }
     }
-    Some((ideal_pos, min_fuel))
+    Ok(Some((ideal_pos, min_fuel)))
 }
 
-struct CrabPositionMap {
-    pos_map: BTreeMap<CrabPos, u32>,
-    total: u32
+/// The total weight of the crabs at each position.
+pub struct CrabPositionMap {
+    pos_map: BTreeMap<CrabPos, u128>,
+    total: u128     // wide enough that weights can't overflow
 }
 impl CrabPositionMap {
     fn new() -> Self {
@@ -44,39 +81,50 @@ impl CrabPositionMap {
         }
     }
 
-    fn add_crabs(&mut self, pos: CrabPos, count: u32) {
-        *self.pos_map.entry(pos).or_insert(0) += count;
-        self.total += count;
+    fn add_crabs(&mut self, pos: CrabPos, weight: CrabWeight) {
+        *self.pos_map.entry(pos).or_insert(0) += weight as u128;
+        self.total += weight as u128;
     }
 
-    #[inline]
-    fn calc_fuel(&self, align_pos: CrabPos, fuel_formula: fn(CrabPos) -> u32) -> u32 {
-        self.pos_map.iter().fold(0, |acc, (pos, count)| {
-            let distance = match *pos > align_pos {
-                true => pos - align_pos,
-                false => align_pos - pos
-            };
-            acc + count * fuel_formula(distance)
-        })
+    pub fn from_positions(all_pos: &[CrabPos]) -> Self {
+        let mut pos_map = CrabPositionMap::new();
+        for &pos in all_pos {
+            pos_map.add_crabs(pos, 1);
+        }
+        pos_map
     }
-    #[inline]
-    fn calc_fuel_p1(&self, align_pos: CrabPos) -> u32 {
-        self.calc_fuel(align_pos, |distance| distance as u32)
+
+    /// Crabs at the given positions, whose fuel use is multiplied by their weights. Every weight must be positive,
+    /// so the total weight is only 0 when there are no crabs.
+    pub fn from_weighted_positions(crabs: &[(CrabPos, CrabWeight)]) -> Result<Self, ZeroWeight> {
+        let mut pos_map = CrabPositionMap::new();
+        for &(pos, weight) in crabs {
+            if weight == 0 { return Err(ZeroWeight); }
+            pos_map.add_crabs(pos, weight);
+        }
+        Ok(pos_map)
     }
+
+    /// The total fuel for every crab to move to `align_pos`, for a `fuel_formula` returning `None` on overflow.
     #[inline]
-    fn calc_fuel_p2(&self, align_pos: CrabPos) -> u32 {
-        self.calc_fuel(align_pos, |distance| (distance as u32 * (distance + 1) as u32) / 2)
+    pub fn calc_fuel<F: Fn(CrabPos) -> Option<Fuel>>(&self, align_pos: CrabPos, fuel_formula: F)
+        -> Result<Fuel, FuelOverflow> {
+        let fuel = self.pos_map.iter().try_fold(0u128, |acc, (&pos, &weight)| {
+            acc.checked_add(weight.checked_mul(fuel_formula(pos.abs_diff(align_pos))? as u128)?)
+        });
+        fuel.and_then(|fuel| Fuel::try_from(fuel).ok()).ok_or(FuelOverflow)
     }
 
-    fn find_ideal_pos_p1(&self) -> Option<(CrabPos, CrabPos, u32)> {
-        if self.total == 0 { return None; }
+    // the range of positions where the part 1 fuel is minimal, and that fuel
+    fn find_min_zone_p1(&self) -> Result<Option<(CrabPos, CrabPos, Fuel)>, FuelOverflow> {
+        if self.total == 0 { return Ok(None); }
         let mut crab_count = 0;
         let mut start = CrabPos::MAX;
         let mut end = 0;
         for (pos, count) in self.pos_map.iter() {   // BTreeMap lets us iterate pos from left to right
             crab_count += count;
             // cost function slope to the right of pos = 2*crab_count - self.total
-            match (2*crab_count).cmp(&self.total) {     // don't compute slope to avoid casting to signed integer
+            match crab_count.cmp(&(self.total - crab_count)) {  // compare halves to avoid overflow and casting
                 Ordering::Less => (),   // -ve slope, minimum not reached yet
                 Ordering::Equal => {    // 0 slope, minimum occurs on an interval, and the left edge is found
                     start = *pos;
@@ -90,78 +138,149 @@ impl CrabPositionMap {
                 }
             }
         }
-        Some((start, end, self.calc_fuel_p1(start)))
+        Ok(Some((start, end, self.calc_fuel(start, fuel_formula_p1)?)))
     }
 
-    fn find_ideal_pos_p2(&self) -> Option<(CrabPos, u32)> {
-        if self.total == 0 { return None; }
-        let mean = self.pos_map.iter()
-            .fold(0, |acc, (pos, count)| acc + (*pos as u32)*(*count)) as f32
-            / self.total as f32;
-        let rounded_mean = mean.round() as CrabPos;
-        let candidates = [rounded_mean-1, rounded_mean, rounded_mean+1];
-
-        candidates.iter()
-            .map(|align_pos| (*align_pos, self.calc_fuel_p2(*align_pos)))
-            .min_by(|x, y| {
-                x.1.cmp(&y.1)
+    // the first position with at least half of the weight at or to the left of it
+    fn weighted_median(&self) -> Option<CrabPos> {
+        let mut crab_count = 0;
+        self.pos_map.iter()
+            .find(|(_, &count)| {
+                crab_count += count;
+                crab_count >= self.total - crab_count
             })
+            .map(|(&pos, _)| pos)
+    }
+
+    /// The part 1 alignment, which is at the weighted median of the positions (the lowest one if several positions
+    /// use the least fuel).
+    pub fn find_ideal_pos_p1(&self) -> Result<Option<Alignment>, FuelOverflow> {
+        self.weighted_median()
+            .map(|median| Ok((median, self.calc_fuel(median, fuel_formula_p1)?)))
+            .transpose()
     }
+
+    /// The part 2 alignment. The slope of the total fuel at `x` is `W(x - mean) + (weight left of x - weight right
+    /// of x)/2` for a total weight `W`, so the minimum lies within half a position of the weighted mean, and only the
+    /// positions from the floor to the ceiling of that range need checking.
+    pub fn find_ideal_pos_p2(&self) -> Result<Option<Alignment>, FuelOverflow> {
+        let (&min_pos, &max_pos) = match (self.pos_map.keys().next(), self.pos_map.keys().next_back()) {
+            (Some(min_pos), Some(max_pos)) => (min_pos, max_pos),
+            _ => return Ok(None),
+        };
+        // the mean is `min_pos + quotient + remainder/total`, measured from `min_pos` to keep the sum small
+        let sum = self.pos_map.iter().try_fold(0u128, |acc, (&pos, &weight)| {
+            acc.checked_add(((pos - min_pos) as u128).checked_mul(weight)?)
+        });
+        let sum = match sum {
+            Some(sum) => sum,
+            None => return self.find_ideal_pos_convex(fuel_formula_p2),
+        };
+        let (quotient, remainder) = (sum / self.total, sum % self.total);
+        let lower = if remainder >= self.total - remainder { quotient } else { quotient.saturating_sub(1) };
+        let upper = quotient + if remainder <= self.total - remainder { 1 } else { 2 };
+        let upper = upper.min((max_pos - min_pos) as u128);
+
+        let mut ideal: Option<Alignment> = None;
+        for offset in lower..=upper {
+            let align_pos = min_pos + offset as CrabPos;
+            let fuel = self.calc_fuel(align_pos, fuel_formula_p2)?;
+            if ideal.is_none_or(|(_, min_fuel)| fuel < min_fuel) { ideal = Some((align_pos, fuel)); }
+        }
+        Ok(ideal)
+    }
+
+    /// The alignment using the least fuel for any `fuel_formula` which is convex and non-decreasing in the distance
+    /// (the lowest position if there are several). The total fuel is then convex, so it is found by a ternary search
+    /// which narrows the range of positions by comparing the fuel at neighbouring positions. This fails if the fuel
+    /// overflows at any position it compares, even when it doesn't at the minimum.
+    pub fn find_ideal_pos_convex<F: Fn(CrabPos) -> Option<Fuel>>(&self, fuel_formula: F)
+        -> Result<Option<Alignment>, FuelOverflow> {
+        let (mut low, mut high) = match (self.pos_map.keys().next(), self.pos_map.keys().next_back()) {
+            (Some(&min_pos), Some(&max_pos)) => (min_pos, max_pos),
+            _ => return Ok(None),
+        };
+        while low < high {
+            let mid = low + (high - low)/2;
+            // the fuel stops decreasing at the minimum
+            if self.calc_fuel(mid, &fuel_formula)? <= self.calc_fuel(mid + 1, &fuel_formula)? {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Ok(Some((low, self.calc_fuel(low, &fuel_formula)?)))
+    }
+}
+
+pub struct Day7;
+impl Solution for Day7 {
+    type Input = Vec<CrabPos>;
+    type Answer1 = Fuel;
+    type Answer2 = Fuel;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        let line = file_data.trim_end();
+        line.split(',')
+            .map(|s| s.trim().parse::<CrabPos>().map_err(|e| PuzzleError::at(1, column_of(line, s), e)))
+            .collect()
+    }
+
+    fn part1(all_pos: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        CrabPositionMap::from_positions(all_pos).find_ideal_pos_p1()?
+            .map(|(_, fuel)| fuel)
+            .ok_or_else(no_crabs)
+    }
+
+    fn part2(all_pos: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        CrabPositionMap::from_positions(all_pos).find_ideal_pos_p2()?
+            .map(|(_, fuel)| fuel)
+            .ok_or_else(no_crabs)
+    }
+}
+
+fn no_crabs() -> PuzzleError {
+    PuzzleError::no_solution("there are no crabs to align")
 }
 
-pub fn day7_main(file_data: &str) -> ((CrabPos, u32), (CrabPos, u32)) {
-    // Method 1: Brute force search
-    let all_positions = file_data
-        .split(',').enumerate()
-        .map(|(i, s)| s.parse::<CrabPos>().unwrap_or_else(|e| {
-            eprintln!("Error parsing the position of crab {}! : ", i);
-            eprintln!("{}", e);
-            panic!();
-        }))
-        .collect::<Vec<CrabPos>>();
+pub fn day7_main(file_data: &str) -> Result<(Alignment, Alignment), PuzzleError> {
+    // Method 1: Optimized by doing some math
+    let all_positions = Day7::parse(file_data)?;
+    let pos_map = CrabPositionMap::from_positions(&all_positions);
+    let (p1_min_left, p1_min_right, p1_min_fuel) = pos_map.find_min_zone_p1()?
+        .ok_or_else(no_crabs)?;
+    let (p2_min_pos, p2_min_fuel) = pos_map.find_ideal_pos_p2()?
+        .ok_or_else(no_crabs)?;
+
+
+    // Method 2: Brute force search, only used to check the answers when the positions are close enough together
     let min_max_pos = all_positions.iter()
         .fold((CrabPos::MAX, CrabPos::MIN), |mut acc, pos| {
             if *pos < acc.0 { acc.0 = *pos; }
             if *pos > acc.1 { acc.1 = *pos; }
             acc
         });
-    let (ideal_pos_p1, min_fuel_p1) =
-        find_ideal_alignment(&all_positions, min_max_pos, fuel_formula_p1)
-        .expect("Error finding part 1 answer using method 1!");
-    let (ideal_pos_p2, min_fuel_p2) =
-        find_ideal_alignment(&all_positions, min_max_pos, fuel_formula_p2)
-        .expect("Error finding part 2 answer using method 1!");
-
-
-    // Method 2: Optimized by doing some math
-    let mut all_positions = CrabPositionMap::new();
-    file_data.split(',').enumerate()
-        .for_each(|(i, s)| {
-            let pos = s.parse::<CrabPos>().unwrap_or_else(|e| {
-                eprintln!("Error parsing the position of crab {}! : ", i);
-                eprintln!("{}", e);
-                panic!();
-            });
-            all_positions.add_crabs(pos, 1);
-        });
-    let (p1_min_left, p1_min_right, p1_min_fuel) = all_positions.find_ideal_pos_p1()
-        .expect("Error finding part 1 answer using method 2!");
-    let (p2_min_pos, p2_min_fuel) = all_positions.find_ideal_pos_p2()
-        .expect("Error finding part 2 answer using method 2!");
-
+    if min_max_pos.1 - min_max_pos.0 <= MAX_BRUTE_FORCE_RANGE {
+        let (ideal_pos_p1, min_fuel_p1) =
+            find_ideal_alignment(&all_positions, min_max_pos, fuel_formula_p1)?
+            .ok_or_else(no_crabs)?;
+        let (ideal_pos_p2, min_fuel_p2) =
+            find_ideal_alignment(&all_positions, min_max_pos, fuel_formula_p2)?
+            .ok_or_else(no_crabs)?;
 
-    // Check if different methods give same result
-    if !((p1_min_left <= ideal_pos_p1) && (ideal_pos_p1 <= p1_min_right) && (min_fuel_p1 == p1_min_fuel)) {
-        eprintln!("Part 1:");
-        eprintln!("Method 1 Answer: Position {}, Fuel {}", ideal_pos_p1, min_fuel_p1);
-        eprintln!("Method 2 Answer: Position {}..={}, Fuel {}", p1_min_left, p1_min_right, p1_min_fuel);
-        panic!("Part 1 answers don't match!");
-    }
-    if !((p2_min_pos == ideal_pos_p2) && (min_fuel_p2 == p2_min_fuel)) {
-        eprintln!("Part 2:");
-        eprintln!("Method 1 Answer: Position {}, Fuel {}", ideal_pos_p2, min_fuel_p2);
-        eprintln!("Method 2 Answer: Position {}, Fuel {}", p2_min_pos, p2_min_fuel);
-        panic!("Part 2 answers don't match!");
+        // Check if different methods give same result
+        if !((p1_min_left <= ideal_pos_p1) && (ideal_pos_p1 <= p1_min_right) && (min_fuel_p1 == p1_min_fuel)) {
+            eprintln!("Part 1:");
+            eprintln!("Method 1 Answer: Position {}..={}, Fuel {}", p1_min_left, p1_min_right, p1_min_fuel);
+            eprintln!("Method 2 Answer: Position {}, Fuel {}", ideal_pos_p1, min_fuel_p1);
+            panic!("Part 1 answers don't match!");
+        }
+        if !((p2_min_pos == ideal_pos_p2) && (min_fuel_p2 == p2_min_fuel)) {
+            eprintln!("Part 2:");
+            eprintln!("Method 1 Answer: Position {}, Fuel {}", p2_min_pos, p2_min_fuel);
+            eprintln!("Method 2 Answer: Position {}, Fuel {}", ideal_pos_p2, min_fuel_p2);
+            panic!("Part 2 answers don't match!");
+        }
     }
 
     // Output answers
@@ -169,19 +288,88 @@ pub fn day7_main(file_data: &str) -> ((CrabPos, u32), (CrabPos, u32)) {
     println!("gives the minimum fuel consumption of {}.", p1_min_fuel);
     print!("[Part 2] The horizontal position of {} ", p2_min_pos);
     println!("gives the minimum fuel consumption of {}.", p2_min_fuel);
-    ((ideal_pos_p1, min_fuel_p1), (ideal_pos_p2, min_fuel_p2))
+    Ok(((p1_min_left, p1_min_fuel), (p2_min_pos, p2_min_fuel)))
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
 
     #[test]
     fn it_works() {
         let test_data = "16,1,2,0,4,2,7,1,2,14";
-        let (part1, part2) = day7_main(test_data);
+        let (part1, part2) = day7_main(test_data).unwrap();
         assert_eq!(part1, (2, 37));
         assert_eq!(part2, (5, 168));
+        // far too wide to search every position
+        assert_eq!(day7_main("0,6000000000").unwrap(), ((0, 6000000000), (3000000000, 9000000003000000000)));
+
+        let all_pos = Day7::parse(test_data).unwrap();
+        let pos_map = CrabPositionMap::from_positions(&all_pos);
+        assert_eq!(pos_map.find_ideal_pos_p1(), Ok(Some((2, 37))));
+        assert_eq!(pos_map.find_ideal_pos_convex(fuel_formula_p1), Ok(Some((2, 37))));
+        assert_eq!(pos_map.find_ideal_pos_convex(fuel_formula_p2), Ok(Some((5, 168))));
+        assert_eq!(pos_map.find_ideal_pos_convex(|d: CrabPos| d.checked_pow(2)), Ok(Some((5, 291))));
+        assert_eq!(CrabPositionMap::from_positions(&[]).find_ideal_pos_p2(), Ok(None));
+        assert_eq!(CrabPositionMap::from_positions(&[0, 0]).find_ideal_pos_p2(), Ok(Some((0, 0))));
+
+        // weighted crabs, and positions and fuel beyond 32 bits
+        let pos_map = CrabPositionMap::from_weighted_positions(&[(0, 1), (10, 3), (20, 1)]).unwrap();
+        assert_eq!(pos_map.find_ideal_pos_p1(), Ok(Some((10, 20))));
+        assert_eq!(pos_map.find_ideal_pos_p2(), Ok(Some((10, 110))));
+        let far = 1 << 40;
+        let crabs = [(far, 1 << 30), (far + 2, 1 << 30), (far + 4, 1)];
+        let pos_map = CrabPositionMap::from_weighted_positions(&crabs).unwrap();
+        assert_eq!(pos_map.find_ideal_pos_p1(), Ok(Some((far + 2, (1 << 31) + 2))));
+        assert_eq!(pos_map.find_ideal_pos_p2(), Ok(Some((far + 1, (1 << 31) + 6))));
+        assert_eq!(fuel_formula_p2(u64::MAX), None);
+        assert_eq!(fuel_formula_p2((1 << 32) - 1), Some((1 << 63) - (1 << 31)));
+        let pos_map = CrabPositionMap::from_positions(&[0, u64::MAX]);
+        assert_eq!(pos_map.find_ideal_pos_p1(), Ok(Some((0, u64::MAX))));
+        assert_eq!(pos_map.find_ideal_pos_p2(), Err(FuelOverflow));
+        let pos_map = CrabPositionMap::from_weighted_positions(&[(0, u64::MAX), (2, 1)]).unwrap();
+        assert_eq!(pos_map.find_ideal_pos_p1(), Ok(Some((0, 2))));
+        assert_eq!(pos_map.find_ideal_pos_convex(fuel_formula_p1), Err(FuelOverflow));
+        assert_eq!(pos_map.calc_fuel(1, fuel_formula_p1), Err(FuelOverflow));
+        assert_eq!(CrabPositionMap::from_weighted_positions(&[(1, 0)]).err(), Some(ZeroWeight));
+        assert_eq!(CrabPositionMap::from_weighted_positions(&[(1, 2), (3, 0)]).err(), Some(ZeroWeight));
+        let no_crabs = CrabPositionMap::from_weighted_positions(&[]).unwrap();
+        assert_eq!(no_crabs.find_ideal_pos_p1(), Ok(None));
+        assert_eq!(no_crabs.find_min_zone_p1(), Ok(None));
+        assert_eq!(no_crabs.find_ideal_pos_p2(), Ok(None));
+        assert_eq!(no_crabs.find_ideal_pos_convex(fuel_formula_p2), Ok(None));
+
+        // every strategy finds the same minimum fuel (and the same lowest position, except for part 2's checks around
+        // the mean, which may find another with the same fuel)
+        let mut rng = StdRng::seed_from_u64(2021);
+        for _ in 0..200 {
+            let all_pos = (0..rng.gen_range(1..30)).map(|_| rng.gen_range(0..100)).collect::<Vec<CrabPos>>();
+            let pos_map = CrabPositionMap::from_positions(&all_pos);
+            let (&min_pos, &max_pos) = (all_pos.iter().min().unwrap(), all_pos.iter().max().unwrap());
+            for fuel_formula in [fuel_formula_p1, fuel_formula_p2] {
+                let brute_force = find_ideal_alignment(&all_pos, (min_pos, max_pos), fuel_formula);
+                assert_eq!(pos_map.find_ideal_pos_convex(fuel_formula), brute_force);
+            }
+            let brute_force_p1 = find_ideal_alignment(&all_pos, (min_pos, max_pos), fuel_formula_p1).unwrap();
+            assert_eq!(pos_map.find_ideal_pos_p1().unwrap(), brute_force_p1);
+            let (start, end, fuel) = pos_map.find_min_zone_p1().unwrap().unwrap();
+            assert!(start <= end && Some((start, fuel)) == brute_force_p1);
+            let brute_force_p2 = find_ideal_alignment(&all_pos, (min_pos, max_pos), fuel_formula_p2).unwrap();
+            let p2_fuel = pos_map.find_ideal_pos_p2().unwrap().map(|(_, fuel)| fuel);
+            assert_eq!(p2_fuel, brute_force_p2.map(|(_, fuel)| fuel));
+
+            // weights are the same as repeating crabs
+            let weighted = all_pos.iter().map(|&pos| (pos, rng.gen_range(1..4))).collect::<Vec<_>>();
+            let repeated = weighted.iter().flat_map(|&(pos, weight)| vec![pos; weight as usize]).collect::<Vec<_>>();
+            let weighted = CrabPositionMap::from_weighted_positions(&weighted).unwrap();
+            let repeated = CrabPositionMap::from_positions(&repeated);
+            assert_eq!(weighted.find_ideal_pos_p1(), repeated.find_ideal_pos_p1());
+            assert_eq!(weighted.find_ideal_pos_p2(), repeated.find_ideal_pos_p2());
+            let convex = weighted.find_ideal_pos_convex(fuel_formula_p2);
+            assert_eq!(convex, repeated.find_ideal_pos_convex(fuel_formula_p2));
+        }
     }
 }
\ No newline at end of file
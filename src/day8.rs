@@ -1,115 +1,307 @@
-fn contains_digit(s: &str, other: &str) -> bool {
-    other.chars().all(|c| s.contains(c))
-}
-fn is_same_digit(s: &str, other: &str) -> bool {
-    if s.len() != other.len() { return false; }
-    other.chars().all(|c| s.contains(c))
-}
-
-fn decode_entry_string(s: &str) -> Option<u16> {
-    let mut sections = s.split('|');
-    let mut digit_patterns: [Option<&str>; 10] = [None; 10];
-
-    // put the known words into digit_patterns and collect the remaning unknown words
-    let todo = sections.next()?
-        .split_whitespace().filter_map(|s| {
-            let mut ret_val = None;
-            match s.len() {
-                2 => digit_patterns[1] = Some(s),
-                3 => digit_patterns[7] = Some(s),
-                4 => digit_patterns[4] = Some(s),
-                7 => digit_patterns[8] = Some(s),
-                _ => ret_val = Some(s)
-            }
-            ret_val
-        })
-        .collect::<Vec<_>>();
-    let output_strings = sections.next()?.split_whitespace().collect::<Vec<_>>();
-    if output_strings.len() != 4 { return None; }
-
-    // check if '1', '4', '7', '8' were successfully found
-    for i in [1, 4, 7, 8] {
-        digit_patterns[i]?;
-    }
-
-    // partition by checking if the digit is contains '7'
-    let (mut contains_7, mut todo): (Vec<_>, Vec<_>) = todo.into_iter().partition(|&s| {
-        contains_digit(s, digit_patterns[7].unwrap())
-    });
-    if contains_7.len() != 3 { return None; }   // precisely 3 digits satisfies the condition
-    if todo.len() != 3 { return None; }   // precisely 3 digits satisfies the condition
-
-
-    // contains 7 and length 5 ---> '3'
-    let pos = contains_7.iter().position(|&s| s.len() == 5)?;
-    digit_patterns[3] = Some(contains_7[pos]);
-    contains_7.remove(pos);
-
-    // contains 7 and 4 ---> '9'
-    let pos = contains_7.iter().position(|&s| contains_digit(s, digit_patterns[4].unwrap()))?;
-    digit_patterns[9] = Some(contains_7[pos]);
-    contains_7.remove(pos);
-
-    // last one containing 7 ---> '0'
-    digit_patterns[0] = Some(contains_7.pop().unwrap());    // assumes the words actually represent the 10 digits
-
-    // doesn't contain '7' and length 6 ---> '6'
-    let pos = todo.iter().position(|&s| s.len() == 6)?;
-    digit_patterns[6] = Some(todo[pos]);
-    todo.remove(pos);
-
-    // doesn't contain '7' and contained in '9' ---> '5'
-    let pos = todo.iter().position(|&s| contains_digit(digit_patterns[9].unwrap(), s))?;
-    digit_patterns[5] = Some(todo[pos]);
-    todo.remove(pos);
-
-    // last one ---> '2'
-    digit_patterns[2] = Some(todo.pop().unwrap());  // assumes the words actually represent the 10 digits
-
-    // get output digits
-    let mut output = 0;
-    // println!("digit_patterns = {:?}", digit_patterns);
-    // println!("output_strings: {:?}", output_strings);
-    for i in 0..output_strings.len() {
-        let num = digit_patterns.iter().position(|word| {
-            is_same_digit(word.unwrap(), output_strings[output_strings.len()-1-i])
-        })?;
-        output += (num as u16) * u16::pow(10, i as u32);
-    }
-
-    Some(output)
-}
-
-
-pub fn day8_main(file_data: &str) -> (u16, u32) {
+use std::fmt;
+use std::error::Error;
+use std::io::BufRead;
+use crate::{read_lines, Solution, PuzzleError};
+
+#[derive(Debug)]
+pub enum ParseEntryError {
+    MissingDelimiter, InvalidPatternCount(usize), InvalidOutputCount(usize)
+}
+impl fmt::Display for ParseEntryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseEntryError::MissingDelimiter => write!(f, "expected the patterns and outputs to be separated by a single '|'"),
+            ParseEntryError::InvalidPatternCount(n) => write!(f, "expected 10 patterns, found {}", n),
+            ParseEntryError::InvalidOutputCount(n) => write!(f, "expected 4 outputs, found {}", n),
+        }
+    }
+}
+impl Error for ParseEntryError {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DigitTableError {
+    TooManySegments(usize), TooManyDigits(usize), InvalidSegment(char), DuplicateDigit(usize)
+}
+impl fmt::Display for DigitTableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DigitTableError::TooManySegments(n) => write!(f, "expected at most {} segments, found {}", MAX_SEGMENTS, n),
+            DigitTableError::TooManyDigits(n) => write!(f, "expected at most {} digits, found {}", MAX_DIGITS, n),
+            DigitTableError::InvalidSegment(c) => write!(f, "invalid segment '{}'", c),
+            DigitTableError::DuplicateDigit(i) => write!(f, "digit {} lights the same segments as an earlier one", i),
+        }
+    }
+}
+impl Error for DigitTableError {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    InvalidWire(char), Inconsistent, Ambiguous, UnknownDigit(String)
+}
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::InvalidWire(c) => write!(f, "invalid wire '{}'", c),
+            DecodeError::Inconsistent => write!(f, "no wiring of the segments shows every pattern as a digit"),
+            DecodeError::Ambiguous => write!(f, "more than one wiring of the segments shows every pattern as a digit"),
+            DecodeError::UnknownDigit(s) => write!(f, "the output \"{}\" is not a digit", s),
+        }
+    }
+}
+impl Error for DecodeError {}
+
+const MAX_SEGMENTS: usize = 26;
+const MAX_DIGITS: usize = 64;
+
+// the segments (or wires) named by the letters of `s` as a bitmask, where 'a' is bit 0
+fn to_mask(s: &str, segment_count: usize) -> Result<u32, char> {
+    s.chars().try_fold(0, |mask, c| {
+        let i = (c as u32).wrapping_sub('a' as u32);
+        if i as usize >= segment_count { return Err(c); }
+        Ok(mask | 1 << i)
+    })
+}
+
+/// The segments of a display which are lit for each of its digits, with segments named by letters from 'a'.
+#[derive(Debug, Clone)]
+pub struct DigitTable {
+    segment_count: usize,
+    digits: Vec<u32>,
+}
+impl DigitTable {
+    /// A table of `digits`, each given as the letters of its lit segments, on a display with `segment_count` segments.
+    pub fn new(segment_count: usize, digits: &[&str]) -> Result<Self, DigitTableError> {
+        if segment_count > MAX_SEGMENTS { return Err(DigitTableError::TooManySegments(segment_count)); }
+        if digits.len() > MAX_DIGITS { return Err(DigitTableError::TooManyDigits(digits.len())); }
+        let mut masks = Vec::with_capacity(digits.len());
+        for (i, digit) in digits.iter().enumerate() {
+            let mask = to_mask(digit, segment_count).map_err(DigitTableError::InvalidSegment)?;
+            if masks.contains(&mask) { return Err(DigitTableError::DuplicateDigit(i)); }
+            masks.push(mask);
+        }
+        Ok(DigitTable { segment_count, digits: masks })
+    }
+
+    /// The digits 0-9 of the seven-segment display in the puzzle.
+    pub fn seven_segment() -> Self {
+        DigitTable::new(7, &["abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg"])
+            .unwrap()
+    }
+
+    /// The hexadecimal digits 0-9 and A-F (with b and d in lower case) of the seven-segment display in the puzzle.
+    pub fn seven_segment_hex() -> Self {
+        DigitTable::new(7, &["abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+            "abcdef", "bdefg", "abeg", "cdefg", "abdeg", "abde"]).unwrap()
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.segment_count
+    }
+
+    pub fn digit_count(&self) -> usize {
+        self.digits.len()
+    }
+
+    // the digit lighting exactly the segments in `mask`
+    fn digit_of(&self, mask: u32) -> Option<usize> {
+        self.digits.iter().position(|&digit| digit == mask)
+    }
+}
+
+/// The segment which each wire of a display is connected to.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Wiring(Vec<usize>);
+impl Wiring {
+    /// The segment lit by `wire`, if it is one of the display's wires.
+    pub fn segment(&self, wire: char) -> Option<char> {
+        let i = (wire as u32).wrapping_sub('a' as u32) as usize;
+        self.0.get(i).map(|&segment| (b'a' + segment as u8) as char)
+    }
+
+    fn segments_of(&self, wires: u32) -> u32 {
+        self.0.iter().enumerate()
+            .filter(|&(wire, _)| wires & 1 << wire != 0)
+            .fold(0, |mask, (_, &segment)| mask | 1 << segment)
+    }
+}
+
+// finds the wirings showing each pattern as a digit, by choosing a digit for each pattern in turn and narrowing down
+// the segments each wire could be connected to
+struct WiringSearch<'a> {
+    table: &'a DigitTable,
+    patterns: Vec<u32>,
+    wirings: Vec<Wiring>,
+}
+impl WiringSearch<'_> {
+    // stops after finding two wirings, which is enough to know the entry is ambiguous
+    fn search(&mut self, pattern_index: usize, used_digits: u64, candidates: &[u32]) {
+        if self.wirings.len() > 1 { return; }
+        let pattern = match self.patterns.get(pattern_index) {
+            Some(&pattern) => pattern,
+            None => return self.assign_wires(0, 0, candidates, &mut Vec::with_capacity(candidates.len())),
+        };
+        for (digit, &segments) in self.table.digits.iter().enumerate() {
+            if used_digits & 1 << digit != 0 || segments.count_ones() != pattern.count_ones() { continue; }
+            // wires in the pattern light the digit's segments, and the others don't
+            let narrowed = candidates.iter().enumerate()
+                .map(|(wire, &c)| if pattern & 1 << wire != 0 { c & segments } else { c & !segments })
+                .collect::<Vec<_>>();
+            if narrowed.contains(&0) { continue; }
+            self.search(pattern_index + 1, used_digits | 1 << digit, &narrowed);
+        }
+    }
+
+    // connects each remaining wire to a different one of its candidate segments
+    fn assign_wires(&mut self, wire: usize, used_segments: u32, candidates: &[u32], wiring: &mut Vec<usize>) {
+        if self.wirings.len() > 1 { return; }
+        if wire == candidates.len() {
+            self.wirings.push(Wiring(wiring.clone()));
+            return;
+        }
+        let mut available = candidates[wire] & !used_segments;
+        while available != 0 {
+            let segment = available.trailing_zeros() as usize;
+            available &= available - 1;
+            wiring.push(segment);
+            self.assign_wires(wire + 1, used_segments | 1 << segment, candidates, wiring);
+            wiring.pop();
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+    patterns: Vec<String>,
+    outputs: Vec<String>,
+}
+impl Entry {
+    fn from_str(s: &str) -> Result<Self, ParseEntryError> {
+        let mut sections = s.split('|');
+        if let (Some(patterns), Some(outputs), None) = (sections.next(), sections.next(), sections.next()) {
+            let patterns = patterns.split_whitespace().map(str::to_owned).collect::<Vec<_>>();
+            let outputs = outputs.split_whitespace().map(str::to_owned).collect::<Vec<_>>();
+            if patterns.len() != 10 { return Err(ParseEntryError::InvalidPatternCount(patterns.len())); }
+            if outputs.len() != 4 { return Err(ParseEntryError::InvalidOutputCount(outputs.len())); }
+            return Ok(Entry { patterns, outputs });
+        }
+        Err(ParseEntryError::MissingDelimiter)
+    }
+
+    /// An entry with any number of unique signal patterns and output values, each written as the letters of its
+    /// lit wires.
+    pub fn new(patterns: Vec<String>, outputs: Vec<String>) -> Self {
+        Entry { patterns, outputs }
+    }
+
+    /// The only wiring which shows every unique signal pattern as a digit of `table`.
+    pub fn solve_wiring(&self, table: &DigitTable) -> Result<Wiring, DecodeError> {
+        let mut patterns = self.patterns.iter()
+            .map(|s| to_mask(s, table.segment_count).map_err(DecodeError::InvalidWire))
+            .collect::<Result<Vec<_>, _>>()?;
+        patterns.sort_unstable();
+        patterns.dedup();
+        // patterns with the fewest digits of the same length narrow down the wiring the fastest
+        let digits_of_len = |pattern: &u32| {
+            table.digits.iter().filter(|digit| digit.count_ones() == pattern.count_ones()).count()
+        };
+        patterns.sort_by_key(digits_of_len);
+
+        let all_segments = (1 << table.segment_count) - 1;
+        let mut search = WiringSearch { table, patterns, wirings: Vec::new() };
+        search.search(0, 0, &vec![all_segments; table.segment_count]);
+        match search.wirings.len() {
+            0 => Err(DecodeError::Inconsistent),
+            1 => Ok(search.wirings.pop().unwrap()),
+            _ => Err(DecodeError::Ambiguous),
+        }
+    }
+
+    /// The digits of `table` shown by the output values, using the wiring found from the unique signal patterns.
+    pub fn decode(&self, table: &DigitTable) -> Result<Vec<usize>, DecodeError> {
+        let wiring = self.solve_wiring(table)?;
+        self.outputs.iter()
+            .map(|s| {
+                let wires = to_mask(s, table.segment_count).map_err(DecodeError::InvalidWire)?;
+                table.digit_of(wiring.segments_of(wires)).ok_or_else(|| DecodeError::UnknownDigit(s.clone()))
+            })
+            .collect()
+    }
+}
+
+pub struct Day8;
+impl Solution for Day8 {
+    type Input = Vec<Entry>;
+    type Answer1 = u16;
+    type Answer2 = u64;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        // shares the line handling of the reader, so trailing blank lines are skipped either way
+        Self::parse_reader(file_data.as_bytes())
+    }
+
+    fn parse_reader<R: BufRead>(reader: R) -> Result<Self::Input, PuzzleError> {
+        read_lines(reader)
+            .map(|line| {
+                let (line_number, line) = line?;
+                Entry::from_str(&line).map_err(|e| PuzzleError::at_line(line_number, e))
+            })
+            .collect()
+    }
+
+    fn part1(entries: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(entries.iter()
+            .flat_map(|entry| entry.outputs.iter())
+            .fold(0, |acc, s| {
+                acc + match s.len() {
+                     2 | 3 | 4 | 7 => 1,
+                    _ => 0
+                }
+            }))
+    }
+
+    fn part2(entries: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        let table = DigitTable::seven_segment();
+        entries.iter()
+            .enumerate()
+            .try_fold(0, |acc, (i, entry)| {
+                let digits = entry.decode(&table).map_err(|e| PuzzleError::at_line(i+1, e))?;
+                Ok(acc + digits.iter().fold(0, |output, &digit| output*10 + digit as u64))
+            })
+    }
+}
+
+pub fn day8_main(file_data: &str) -> Result<(u16, u64), PuzzleError> {
+    let entries = Day8::parse(file_data)?;
+
     // Part 1
-    let output_digits = file_data.split(&['|', '\n'][..])
-        .skip(1).step_by(2).map(|s| s.split_whitespace());
-
-    let part1_count = output_digits.flatten()
-        .fold(0, |acc, s| {
-            acc + match s.len() {
-                 2 | 3 | 4 | 7 => 1,
-                _ => 0
-            }
-        });
+    let part1_count = Day8::part1(&entries)?;
     println!("There are {} occurences of '1', '4', '7' or '8' in the output digits.", part1_count);
 
     // Part 2
-    let part2_count = file_data.lines()
-        .enumerate()
-        .fold(0, |acc, (i, line)| {
-            acc + decode_entry_string(line)
-                .unwrap_or_else(|| panic!("Unable to decode entry on line {}!", i+1)) as u32
-        });
+    let part2_count = Day8::part2(&entries)?;
     println!("The sum of all output digits is {}.", part2_count);
 
-    (part1_count, part2_count)
+    Ok((part1_count, part2_count))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+
+    // the patterns lighting each digit when wire `w` is connected to segment `wiring[w]`
+    fn scramble(table: &DigitTable, wiring: &[usize], digits: &[usize]) -> Vec<String> {
+        digits.iter()
+            .map(|&digit| {
+                (0..wiring.len())
+                    .filter(|&wire| table.digits[digit] & 1 << wiring[wire] != 0)
+                    .map(|wire| (b'a' + wire as u8) as char)
+                    .collect()
+            })
+            .collect()
+    }
 
     #[test]
     fn it_works() {
@@ -125,6 +317,43 @@ mod tests {
             egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
             gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce";
 
-        assert_eq!(day8_main(test_data), (26, 61229));
+        assert_eq!(day8_main(test_data).unwrap(), (26, 61229));
+
+        let entries = Day8::parse(test_data).unwrap();
+        let wiring = entries[0].solve_wiring(&DigitTable::seven_segment()).unwrap();
+        assert_eq!(wiring.segment('d'), Some('a'));    // 'edb' is 7 and 'be' is 1
+        assert_eq!(wiring.segment('h'), None);
+        assert_eq!(entries[0].decode(&DigitTable::seven_segment()), Ok(vec![8, 3, 9, 4]));
+
+        // hexadecimal digits, and a table with 12 segments, under random wirings
+        let mut rng = StdRng::seed_from_u64(2021);
+        let hex = DigitTable::seven_segment_hex();
+        let twelve = DigitTable::new(12, &["abcdefghijkl", "ab", "bcd", "cdef", "efgh", "ghij", "ijkl", "kla", "acegik",
+            "bdfhjl", "abcfgh", "dekl"]).unwrap();
+        for table in [&hex, &twelve] {
+            let mut wiring = (0..table.segment_count()).collect::<Vec<_>>();
+            wiring.shuffle(&mut rng);
+            let entry = Entry::new(scramble(table, &wiring, &(0..table.digit_count()).collect::<Vec<_>>()),
+                scramble(table, &wiring, &[10, 1, 11, 0]));
+            assert_eq!(entry.solve_wiring(table), Ok(Wiring(wiring)));
+            assert_eq!(entry.decode(table), Ok(vec![10, 1, 11, 0]));
+        }
+
+        // too few patterns to tell the wires apart, patterns which can't all be digits, and outputs which aren't
+        let table = DigitTable::seven_segment();
+        let split = |s: &str| s.split_whitespace().map(str::to_owned).collect::<Vec<_>>();
+        assert_eq!(Entry::new(split("ab abd"), split("ab")).solve_wiring(&table), Err(DecodeError::Ambiguous));
+        assert_eq!(Entry::new(split("ab cd"), split("ab")).solve_wiring(&table), Err(DecodeError::Inconsistent));
+        assert_eq!(Entry::new(split("ab abz"), split("ab")).solve_wiring(&table), Err(DecodeError::InvalidWire('z')));
+        let mut entry = Entry::from_str(test_data.lines().next().unwrap()).unwrap();
+        entry.outputs.push("bc".to_owned());
+        assert_eq!(entry.decode(&table), Err(DecodeError::UnknownDigit("bc".to_owned())));
+        assert_eq!(DigitTable::new(3, &["ab", "ba"]).unwrap_err(), DigitTableError::DuplicateDigit(1));
+        assert_eq!(DigitTable::new(3, &["ad"]).unwrap_err(), DigitTableError::InvalidSegment('d'));
+
+        // trailing blank lines are skipped whether the input is a string or a reader
+        let padded = format!("{}\n\n", test_data);
+        assert_eq!(Day8::parse(&padded).unwrap(), Day8::parse_reader(padded.as_bytes()).unwrap());
+        assert_eq!(Day8::parse(&padded).unwrap().len(), 10);
     }
 }
\ No newline at end of file
@@ -1,22 +1,34 @@
 use std::iter;
+use std::fmt;
+use std::error::Error;
 use std::collections::HashSet;
+use crate::grid::{Grid, Point};
+use crate::{Solution, PuzzleError};
 
-type Point = (u8, u8);
+#[derive(Debug)]
+pub enum ParseHeightMapError {
+    InvalidHeight(char)
+}
+impl fmt::Display for ParseHeightMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseHeightMapError::InvalidHeight(c) => write!(f, "invalid height '{}', expected a digit", c),
+        }
+    }
+}
+impl Error for ParseHeightMapError {}
+
+pub type HeightMap = Grid<u8>;
 type Basin = HashSet<Point>;    // groups a set of points into a Basin
 
-fn group_into_basins(height_map: &[Vec<u8>]) -> Vec<Basin> {
-    let mut map = height_map.iter().enumerate()
-        .flat_map(|(i, row)| {
-            row.iter().enumerate().map(move |(j, &x)| ((i as u8, j as u8), x))
-        })
-        .filter_map(|(p, height)| {
+fn group_into_basins(height_map: &HeightMap) -> Vec<Basin> {
+    let mut map = height_map.enumerate()
+        .filter_map(|(p, &height)| {
             if height == 9 { None }
             else { Some(p) }
         })
         .collect::<HashSet<Point>>();
 
-    let num_of_rows = height_map.len() as u8;
-    let row_length = height_map[0].len() as u8;
     let mut basins = Vec::new();
     let mut stack = Vec::new();
     let mut current_basin;
@@ -26,10 +38,7 @@ fn group_into_basins(height_map: &[Vec<u8>]) -> Vec<Basin> {
         while let Some(p) = stack.pop() {
             map.remove(&p);
             current_basin.insert(p);
-            if p.0 != 0             && map.contains(&(p.0-1, p.1)) { stack.push((p.0-1, p.1)); }    // up
-            if p.0 != num_of_rows-1 && map.contains(&(p.0+1, p.1)) { stack.push((p.0+1, p.1)); }    // down
-            if p.1 != 0             && map.contains(&(p.0, p.1-1)) { stack.push((p.0, p.1-1)); }    // left
-            if p.1 != row_length-1  && map.contains(&(p.0, p.1+1)) { stack.push((p.0, p.1+1)); }    // right
+            stack.extend(height_map.neighbours4(p).filter(|neighbour| map.contains(neighbour)));
         }
         basins.push(current_basin);
     }
@@ -46,7 +55,7 @@ fn find_local_minima_1d<'a>(mut height_map: std::slice::Iter<'a, u8>)
         Some(x) => *x,
         None => u8::MAX   // default to max value, then empty vector will be returned
     };
-    Box::new(height_map.chain(iter::repeat(&u8::MAX).take(1))   // pad on the right
+    Box::new(height_map.chain(iter::once(&u8::MAX))   // pad on the right
         .enumerate()
         .filter_map(move |(i, &next)| {
             let ret_val = match (current < prev) && (current < next) {
@@ -59,60 +68,76 @@ fn find_local_minima_1d<'a>(mut height_map: std::slice::Iter<'a, u8>)
         }))
 }
 // calculates the risk level as per the criteria
-fn get_total_risk_level(height_map: &[Vec<u8>]) -> u16 {
+fn get_total_risk_level(height_map: &HeightMap) -> u16 {
     // find points which are minima in the horizontal direction
-    let horizontal_minima_points = height_map.iter().enumerate()
-        .map(|(i, row)| {
+    let horizontal_minima_points = height_map.rows().enumerate()
+        .flat_map(|(i, row)| {
             iter::repeat(i).zip(find_local_minima_1d(row.iter()))
-        })
-        .flatten();
+        });
 
     // horizontal minima which are also vertical minima are the true minima
-    let num_of_rows = height_map.len();
+    let num_of_rows = height_map.height();
     let true_minima_points = horizontal_minima_points
-        .filter(|(i, j)| {
+        .filter(|&(i, j)| {
             // compare with the points above and below (where they exist)
-            if *i != 0 && height_map[*i-1][*j] <= height_map[*i][*j] { return false; }
-            if *i != num_of_rows-1 && height_map[*i+1][*j] <= height_map[*i][*j] { return false; }
+            if i != 0 && height_map[(i-1, j)] <= height_map[(i, j)] { return false; }
+            if i != num_of_rows-1 && height_map[(i+1, j)] <= height_map[(i, j)] { return false; }
             true
         });
 
-    true_minima_points.fold(0, |acc, (i, j)| {
-        acc + 1 + height_map[i][j] as u16
+    true_minima_points.fold(0, |acc, p| {
+        acc + 1 + height_map[p] as u16
     })
 }
 
 
-pub fn day9_main(file_data: &str) -> (u16, u32) {
-    // Part 1
-    let row_len = file_data.lines().next()
-        .unwrap_or_else(|| {
-            panic!("File is empty!");
-        })
-        .trim().chars().count();
-    let height_map = file_data.lines().enumerate()
-        .map(|(i, line)| {
-            let ret_val = line.trim().chars().enumerate()
-                .map(|(j, c)| c.to_digit(10).unwrap_or_else(|| {
-                    panic!("Error parsing the height at position ({}, {}).", i, j);
-                }) as u8)
-                .collect::<Vec<_>>();
-            if ret_val.len() != row_len {
-                panic!("Error creating height map at line {}! All rows must have the same length.", i+1);
-            }
-            ret_val
+pub fn product_of_largest_basins(height_map: &HeightMap) -> u32 {
+    let basins = group_into_basins(height_map);
+    let mut basin_sizes = basins.iter().map(|basin| basin.len() as u32)
+        .collect::<Vec<_>>();
+    basin_sizes.sort_unstable();
+    basin_sizes.iter().rev().take(3).product()
+}
+
+// the first attempt at part 2, kept to compare against `product_of_largest_basins`
+pub fn product_of_largest_basins_first_try(height_map: &HeightMap) -> u32 {
+    let basin_map = p2_first_try::BasinMap::from_height_map(height_map);
+    let mut basin_sizes = basin_map.basins.values()
+        .map(|basin| basin.len() as u32).collect::<Vec<_>>();
+    basin_sizes.sort_unstable();
+    basin_sizes.iter().rev().take(3).product()
+}
+
+pub struct Day9;
+impl Solution for Day9 {
+    type Input = HeightMap;
+    type Answer1 = u16;
+    type Answer2 = u32;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError> {
+        Grid::parse_with(file_data, |c| {
+            c.to_digit(10).map(|x| x as u8).ok_or(ParseHeightMapError::InvalidHeight(c))
         })
-        .collect::<Vec<Vec<_>>>();
+    }
+
+    fn part1(height_map: &Self::Input) -> Result<Self::Answer1, PuzzleError> {
+        Ok(get_total_risk_level(height_map))
+    }
 
-    let part1_answer = get_total_risk_level(&height_map);
+    fn part2(height_map: &Self::Input) -> Result<Self::Answer2, PuzzleError> {
+        Ok(product_of_largest_basins(height_map))
+    }
+}
+
+pub fn day9_main(file_data: &str) -> Result<(u16, u32), PuzzleError> {
+    let height_map = Day9::parse(file_data)?;
+
+    // Part 1
+    let part1_answer = Day9::part1(&height_map)?;
     println!("[Part 1] The total risk level is {}.", part1_answer);
 
     // Part 2
-    let basins = group_into_basins(&height_map);
-    let mut basin_sizes = basins.iter().map(|basin| basin.len() as u32)
-        .collect::<Vec<_>>();
-    basin_sizes.sort_unstable();
-    let part2_answer: u32 = basin_sizes.iter().rev().take(3).product();
+    let part2_answer = Day9::part2(&height_map)?;
 
     // let basin_map = p2_first_try::BasinMap::from_height_map(&height_map);
     // let mut basin_sizes = basin_map.basins.values()
@@ -122,7 +147,7 @@ pub fn day9_main(file_data: &str) -> (u16, u32) {
     // if part2_answer2 != part2_answer { panic!("Part 2 answers don't match!"); }
     println!("[Part 2] The product is {}.", part2_answer);
 
-    (part1_answer, part2_answer)
+    Ok((part1_answer, part2_answer))
 }
 
 
@@ -170,8 +195,8 @@ mod p2_first_try {
     }
     // data structure allowing us to query BasinID by Point and Basin(set of Points) by BasinID
     impl BasinMap {
-        pub fn from_height_map(height_map: &[Vec<u8>]) -> Self {
-            let num_of_rows = height_map.len() as u8;
+        pub fn from_height_map(height_map: &super::HeightMap) -> Self {
+            let num_of_rows = height_map.height() as u8;
             let mut basin_map = BasinMap {
                 basins: HashMap::new(),
                 map: HashMap::new()
@@ -180,7 +205,7 @@ mod p2_first_try {
 
             // connect basins horizontally
             let mut current_id = 0; // increment when the next point cannot belong to the same basin
-            height_map.iter().enumerate().for_each(|(i, row)| {
+            height_map.rows().enumerate().for_each(|(i, row)| {
                 row.iter().enumerate().for_each(|(j, &height)| {
                     if height == 9 { current_id += 1; }
                     else { basin_map.add_basin_point(current_id, (i as u8, j as u8)); }
@@ -205,7 +230,7 @@ mod p2_first_try {
         }
 
         fn add_basin_point(&mut self, id: BasinID, point: Point) {
-            self.basins.entry(id).or_insert_with(Basin::new).insert(point);
+            self.basins.entry(id).or_default().insert(point);
             self.map.insert(point, id);
         }
 
@@ -246,6 +271,6 @@ mod tests {
         8767896789
         9899965678";
 
-        assert_eq!(day9_main(test_data), (15, 1134));
+        assert_eq!(day9_main(test_data).unwrap(), (15, 1134));
     }
 }
\ No newline at end of file
@@ -0,0 +1,93 @@
+use std::io;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// The position of an error in the puzzle input, where lines and columns are numbered from 1.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Location {
+    pub line: usize,
+    pub col: Option<usize>,
+}
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.col {
+            Some(col) => write!(f, "line {}, column {}", self.line, col),
+            None => write!(f, "line {}", self.line),
+        }
+    }
+}
+
+pub type BoxedError = Box<dyn Error + Send + Sync + 'static>;
+
+/// An error from parsing or solving a puzzle input.
+#[derive(Debug)]
+pub enum PuzzleError {
+    // the input is malformed, `location` is `None` when the problem is with the input as a whole
+    InvalidInput { location: Option<Location>, cause: BoxedError },
+    // the input is well-formed but has no answer
+    NoSolution(String),
+    // the input could not be read
+    Io(io::Error),
+}
+impl Display for PuzzleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleError::InvalidInput { location: Some(location), .. } => write!(f, "invalid input at {}", location),
+            PuzzleError::InvalidInput { location: None, .. } => write!(f, "invalid input"),
+            PuzzleError::NoSolution(msg) => write!(f, "no solution: {}", msg),
+            PuzzleError::Io(_) => write!(f, "unable to read the input"),
+        }
+    }
+}
+impl Error for PuzzleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PuzzleError::InvalidInput { cause, .. } => Some(cause.as_ref()),
+            PuzzleError::NoSolution(_) => None,
+            PuzzleError::Io(e) => Some(e),
+        }
+    }
+}
+impl From<io::Error> for PuzzleError {
+    fn from(e: io::Error) -> Self {
+        PuzzleError::Io(e)
+    }
+}
+impl PuzzleError {
+    pub fn invalid<E: Into<BoxedError>>(cause: E) -> Self {
+        PuzzleError::InvalidInput { location: None, cause: cause.into() }
+    }
+
+    pub fn at_line<E: Into<BoxedError>>(line: usize, cause: E) -> Self {
+        PuzzleError::InvalidInput { location: Some(Location { line, col: None }), cause: cause.into() }
+    }
+
+    pub fn at<E: Into<BoxedError>>(line: usize, col: usize, cause: E) -> Self {
+        PuzzleError::InvalidInput { location: Some(Location { line, col: Some(col) }), cause: cause.into() }
+    }
+
+    pub fn no_solution<S: Into<String>>(msg: S) -> Self {
+        PuzzleError::NoSolution(msg.into())
+    }
+
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            PuzzleError::InvalidInput { location, .. } => *location,
+            _ => None,
+        }
+    }
+
+    // moves the location down by `offset` lines, for errors found while parsing a block of the input that does
+    // not start on the first line
+    pub fn offset_lines(mut self, offset: usize) -> Self {
+        if let PuzzleError::InvalidInput { location: Some(ref mut location), .. } = self {
+            location.line += offset;
+        }
+        self
+    }
+}
+
+// the column at which `token` starts within `line`, where `token` must be a subslice of `line`
+pub(crate) fn column_of(line: &str, token: &str) -> usize {
+    token.as_ptr() as usize - line.as_ptr() as usize + 1
+}
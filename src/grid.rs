@@ -0,0 +1,242 @@
+use std::slice;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Index, IndexMut};
+use crate::{BoxedError, PuzzleError};
+
+/// A position in a grid, as (row, column).
+pub type Point = (usize, usize);
+
+// up, down, left, right
+const NEIGHBOURS_4: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+// clockwise, starting from the upper-left
+const NEIGHBOURS_8: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1)];
+
+#[derive(Debug)]
+pub enum ParseGridError {
+    EmptyGrid, InvalidRowLength { expected: usize, found: usize }, InvalidDigit(char)
+}
+impl Display for ParseGridError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseGridError::EmptyGrid => write!(f, "the grid is empty"),
+            ParseGridError::InvalidRowLength { expected, found } =>
+                write!(f, "all rows must have the same length, expected {} but found {}", expected, found),
+            ParseGridError::InvalidDigit(c) => write!(f, "invalid character '{}', expected a digit", c),
+        }
+    }
+}
+impl Error for ParseGridError {}
+
+/// A dynamically sized 2D grid, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    height: usize,
+    width: usize,
+}
+impl<T: Clone> Grid<T> {
+    pub fn new(height: usize, width: usize, init_val: T) -> Self {
+        Grid { cells: vec![init_val; height*width], height, width }
+    }
+}
+impl<T> Grid<T> {
+    /// Creates a grid from its cells in row-major order, or returns `None` if there are not `height*width` cells.
+    pub fn from_vec(height: usize, width: usize, cells: Vec<T>) -> Option<Self> {
+        (cells.len() == height*width).then(|| Grid { cells, height, width })
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.cells
+    }
+
+    /// Parses a grid with one row per line, where `parse_cell` converts each character of a row into a cell.
+    /// Lines are trimmed, and errors are located by the line and column of the offending character.
+    pub fn parse_with<F, E>(s: &str, mut parse_cell: F) -> Result<Self, PuzzleError>
+    where
+        F: FnMut(char) -> Result<T, E>,
+        E: Into<BoxedError>,
+    {
+        let mut cells = Vec::new();
+        let mut width = None;
+        let mut height = 0;
+        for (i, line) in s.trim_end().lines().enumerate() {
+            let indent = line.len() - line.trim_start().len();
+            let row_start = cells.len();
+            for (j, c) in line.trim().chars().enumerate() {
+                cells.push(parse_cell(c).map_err(|e| PuzzleError::at(i+1, indent+j+1, e))?);
+            }
+
+            let row_len = cells.len() - row_start;
+            match width {
+                None => width = Some(row_len),
+                Some(expected) if expected != row_len => {
+                    let e = ParseGridError::InvalidRowLength { expected, found: row_len };
+                    return Err(PuzzleError::at_line(i+1, e));
+                },
+                _ => (),
+            }
+            height += 1;
+        }
+
+        match width {
+            Some(width) if width > 0 => Ok(Grid { cells, height, width }),
+            _ => Err(PuzzleError::invalid(ParseGridError::EmptyGrid)),
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        p.0 < self.height && p.1 < self.width
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        if !self.contains(p) { return None; }
+        Some(&self.cells[p.0 * self.width + p.1])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        if !self.contains(p) { return None; }
+        Some(&mut self.cells[p.0 * self.width + p.1])
+    }
+
+    /// Gets the cell at `p`, where positions outside the grid wrap around to the opposite edge.
+    pub fn get_wrapping(&self, p: (isize, isize)) -> &T {
+        &self[self.wrapping_offset((0, 0), p)]
+    }
+
+    /// The position `delta` away from `p`, or `None` if it is outside the grid.
+    pub fn offset(&self, p: Point, delta: (isize, isize)) -> Option<Point> {
+        let moved = (p.0.checked_add_signed(delta.0)?, p.1.checked_add_signed(delta.1)?);
+        self.contains(moved).then_some(moved)
+    }
+
+    /// The position `delta` away from `p`, wrapping around the edges of the grid.
+    pub fn wrapping_offset(&self, p: Point, delta: (isize, isize)) -> Point {
+        let wrap = |x: usize, dx: isize, size: usize| (x as isize + dx).rem_euclid(size as isize) as usize;
+        (wrap(p.0, delta.0, self.height), wrap(p.1, delta.1, self.width))
+    }
+
+    /// The up, down, left and right neighbours of `p` which are within the grid.
+    pub fn neighbours4(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        NEIGHBOURS_4.iter().filter_map(move |&delta| self.offset(p, delta))
+    }
+
+    /// The neighbours of `p` which are within the grid, including the diagonal ones.
+    pub fn neighbours8(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        NEIGHBOURS_8.iter().filter_map(move |&delta| self.offset(p, delta))
+    }
+
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.cells[i*self.width..(i+1)*self.width]
+    }
+
+    pub fn rows(&self) -> slice::Chunks<'_, T> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, j: usize) -> impl Iterator<Item = &T> + '_ {
+        assert!(j < self.width, "column {} is outside a grid of width {}", j, self.width);
+        self.cells[j..].iter().step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.width).map(move |j| self.column(j))
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.cells.iter_mut()
+    }
+
+    /// All positions in the grid, in row-major order.
+    pub fn positions(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |i| (0..width).map(move |j| (i, j)))
+    }
+
+    /// All cells along with their positions, in row-major order.
+    pub fn enumerate(&self) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid { cells: self.cells.iter().map(f).collect(), height: self.height, width: self.width }
+    }
+}
+impl Grid<u8> {
+    /// Parses a grid of single digits, such as a height map.
+    pub fn parse_digits(s: &str) -> Result<Self, PuzzleError> {
+        Grid::parse_with(s, |c| c.to_digit(10).map(|x| x as u8).ok_or(ParseGridError::InvalidDigit(c)))
+    }
+}
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+    fn index(&self, index: Point) -> &Self::Output {
+        assert!(self.contains(index), "{:?} is outside a grid of size {}x{}", index, self.height, self.width);
+        &self.cells[index.0 * self.width + index.1]
+    }
+}
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, index: Point) -> &mut Self::Output {
+        assert!(self.contains(index), "{:?} is outside a grid of size {}x{}", index, self.height, self.width);
+        &mut self.cells[index.0 * self.width + index.1]
+    }
+}
+// each row is written on its own line, with no separator between cells
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, row) in self.rows().enumerate() {
+            if i != 0 { writeln!(f)?; }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let grid = Grid::parse_digits(
+            "123
+            456").unwrap();
+        assert_eq!((grid.height(), grid.width()), (2, 3));
+        assert_eq!(grid[(1, 2)], 6);
+        assert_eq!(grid.get((2, 0)), None);
+        assert_eq!(*grid.get_wrapping((-1, 3)), 4);
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), vec![2, 5]);
+        assert_eq!(grid.neighbours4((0, 0)).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 5);
+        assert_eq!(grid.to_string(), "123\n456");
+
+        let err = Grid::parse_digits("12\n345").unwrap_err();
+        assert_eq!(err.location(), Some(crate::Location { line: 2, col: None }));
+        let err = Grid::parse_digits("12\n3x").unwrap_err();
+        assert_eq!(err.location(), Some(crate::Location { line: 2, col: Some(2) }));
+    }
+}
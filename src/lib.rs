@@ -0,0 +1,100 @@
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
+pub mod day21;
+pub mod bigint;
+pub mod grid;
+mod answers;
+mod error;
+mod solution;
+
+pub use answers::{AnswerStore, ParseAnswersError, ParseAnswersErrorKind, Verdict};
+pub use error::{BoxedError, Location, PuzzleError};
+pub(crate) use error::column_of;
+pub use solution::{solve, solve_reader, solve_timed, Answers, Extra, Extras, Part, PartAnswers, Solution, Timings};
+pub(crate) use solution::read_lines;
+
+use std::io::BufRead;
+
+pub const LAST_DAY: u8 = 21;
+
+/// Solves the requested `part` of `day`, or returns `None` if there is no solution for `day`.
+pub fn solve_day(day: u8, file_data: &str, part: Option<Part>) -> Option<Result<Answers, PuzzleError>> {
+    solve_day_timed(day, file_data, part).map(|result| result.map(|(answers, _)| answers))
+}
+
+/// Like `solve_day`, but also measures how long parsing and each part took.
+pub fn solve_day_timed(day: u8, file_data: &str, part: Option<Part>)
+    -> Option<Result<(Answers, Timings), PuzzleError>> {
+    let answers = match day {
+        1 => solve_timed::<day1::Day1>(file_data, part),
+        2 => solve_timed::<day2::Day2>(file_data, part),
+        3 => solve_timed::<day3::Day3>(file_data, part),
+        4 => solve_timed::<day4::Day4>(file_data, part),
+        5 => solve_timed::<day5::Day5>(file_data, part),
+        6 => solve_timed::<day6::Day6>(file_data, part),
+        7 => solve_timed::<day7::Day7>(file_data, part),
+        8 => solve_timed::<day8::Day8>(file_data, part),
+        9 => solve_timed::<day9::Day9>(file_data, part),
+        10 => solve_timed::<day10::Day10>(file_data, part),
+        11 => solve_timed::<day11::Day11>(file_data, part),
+        12 => solve_timed::<day12::Day12>(file_data, part),
+        13 => solve_timed::<day13::Day13>(file_data, part),
+        14 => solve_timed::<day14::Day14>(file_data, part),
+        15 => solve_timed::<day15::Day15>(file_data, part),
+        16 => solve_timed::<day16::Day16>(file_data, part),
+        17 => solve_timed::<day17::Day17>(file_data, part),
+        18 => solve_timed::<day18::Day18>(file_data, part),
+        19 => solve_timed::<day19::Day19>(file_data, part),
+        20 => solve_timed::<day20::Day20>(file_data, part),
+        21 => solve_timed::<day21::Day21>(file_data, part),
+        _ => return None,
+    };
+    Some(answers)
+}
+
+/// Like `solve_day`, but the input is read from `reader` and line-based inputs are parsed as they are read.
+pub fn solve_day_reader<R: BufRead>(day: u8, reader: R, part: Option<Part>) -> Option<Result<Answers, PuzzleError>> {
+    let answers = match day {
+        1 => solve_reader::<day1::Day1, R>(reader, part),
+        2 => solve_reader::<day2::Day2, R>(reader, part),
+        3 => solve_reader::<day3::Day3, R>(reader, part),
+        4 => solve_reader::<day4::Day4, R>(reader, part),
+        5 => solve_reader::<day5::Day5, R>(reader, part),
+        6 => solve_reader::<day6::Day6, R>(reader, part),
+        7 => solve_reader::<day7::Day7, R>(reader, part),
+        8 => solve_reader::<day8::Day8, R>(reader, part),
+        9 => solve_reader::<day9::Day9, R>(reader, part),
+        10 => solve_reader::<day10::Day10, R>(reader, part),
+        11 => solve_reader::<day11::Day11, R>(reader, part),
+        12 => solve_reader::<day12::Day12, R>(reader, part),
+        13 => solve_reader::<day13::Day13, R>(reader, part),
+        14 => solve_reader::<day14::Day14, R>(reader, part),
+        15 => solve_reader::<day15::Day15, R>(reader, part),
+        16 => solve_reader::<day16::Day16, R>(reader, part),
+        17 => solve_reader::<day17::Day17, R>(reader, part),
+        18 => solve_reader::<day18::Day18, R>(reader, part),
+        19 => solve_reader::<day19::Day19, R>(reader, part),
+        20 => solve_reader::<day20::Day20, R>(reader, part),
+        21 => solve_reader::<day21::Day21, R>(reader, part),
+        _ => return None,
+    };
+    Some(answers)
+}
@@ -1,14 +1,456 @@
+use std::io::{self, BufRead, BufReader, Read};
 use std::fs;
+use std::env;
 use std::process;
-mod day21;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+use advent_of_code_2021::{
+    solve_day, solve_day_reader, solve_day_timed, AnswerStore, Answers, Extra, ParseAnswersError, Part, PuzzleError, Timings, Verdict, LAST_DAY
+};
+
+const USAGE: &str = "usage: advent_of_code_2021 run <day>|all [--part 1|2] [--input PATH|-] [--time] [--format text|json]
+       advent_of_code_2021 check [<day>] [--answers PATH] [--update]";
+const DEFAULT_ANSWERS: &str = "input_data/answers.toml";
+
+#[derive(Debug)]
+enum CliError {
+    MissingCommand,
+    UnknownCommand(String),
+    MissingDay,
+    InvalidDay(String),
+    InvalidPart(String),
+    InvalidFormat(String),
+    InputWithAll,
+    MissingValue(&'static str),
+    UnexpectedArgument(String),
+    ReadInput(String, io::Error),
+    Solve(u8, PuzzleError),
+    RunFailed(usize),
+    ReadAnswers(String, io::Error),
+    ParseAnswers(String, ParseAnswersError),
+    WriteAnswers(String, io::Error),
+    CheckFailed(usize),
+}
+impl Display for CliError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::MissingCommand => write!(f, "no command given"),
+            CliError::UnknownCommand(s) => write!(f, "unknown command \"{}\"", s),
+            CliError::MissingDay => write!(f, "no day given"),
+            CliError::InvalidDay(s) => write!(f, "invalid day \"{}\", expected a number within 1..={}", s, LAST_DAY),
+            CliError::InvalidPart(s) => write!(f, "invalid part \"{}\", expected 1 or 2", s),
+            CliError::InvalidFormat(s) => write!(f, "invalid format \"{}\", expected text or json", s),
+            CliError::InputWithAll => write!(f, "\"--input\" cannot be used when running all days"),
+            CliError::MissingValue(flag) => write!(f, "missing value for \"{}\"", flag),
+            CliError::UnexpectedArgument(s) => write!(f, "unexpected argument \"{}\"", s),
+            CliError::ReadInput(path, _) => write!(f, "unable to read input \"{}\"", path),
+            CliError::Solve(day, _) => write!(f, "day {} failed", day),
+            CliError::RunFailed(count) => write!(f, "{} day(s) failed", count),
+            CliError::ReadAnswers(path, _) => write!(f, "unable to read answers \"{}\"", path),
+            CliError::ParseAnswers(path, _) => write!(f, "invalid answers file \"{}\"", path),
+            CliError::WriteAnswers(path, _) => write!(f, "unable to write answers \"{}\"", path),
+            CliError::CheckFailed(count) => write!(f, "{} answer(s) failed or changed", count),
+        }
+    }
+}
+impl Error for CliError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CliError::ReadInput(_, e) => Some(e),
+            CliError::Solve(_, e) => Some(e),
+            CliError::ReadAnswers(_, e) => Some(e),
+            CliError::ParseAnswers(_, e) => Some(e),
+            CliError::WriteAnswers(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl CliError {
+    fn is_usage_error(&self) -> bool {
+        matches!(self,
+            CliError::MissingCommand | CliError::UnknownCommand(_) | CliError::MissingDay | CliError::InvalidDay(_)
+            | CliError::InvalidPart(_) | CliError::InvalidFormat(_) | CliError::InputWithAll | CliError::MissingValue(_)
+            | CliError::UnexpectedArgument(_)
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Format {
+    Text, Json
+}
+
+#[derive(Debug)]
+struct RunArgs {
+    day: Option<u8>,    // `None` runs every day
+    part: Option<Part>,
+    input: Option<String>,
+    time: bool,
+    format: Format,
+}
+
+#[derive(Debug)]
+struct CheckArgs {
+    day: Option<u8>,
+    answers: String,
+    update: bool,
+}
+
+fn parse_day(day: String) -> Result<u8, CliError> {
+    match day.trim_start_matches("day").parse::<u8>() {
+        Ok(x) if (1..=LAST_DAY).contains(&x) => Ok(x),
+        _ => Err(CliError::InvalidDay(day)),
+    }
+}
+
+fn parse_run_args<I: Iterator<Item = String>>(mut args: I) -> Result<RunArgs, CliError> {
+    let day = match args.next().ok_or(CliError::MissingDay)? {
+        day if day == "all" => None,
+        day => Some(parse_day(day)?),
+    };
+
+    let mut part = None;
+    let mut input = None;
+    let mut time = false;
+    let mut format = Format::Text;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--part" => {
+                let val = args.next().ok_or(CliError::MissingValue("--part"))?;
+                part = match val.as_str() {
+                    "1" => Some(Part::One),
+                    "2" => Some(Part::Two),
+                    _ => return Err(CliError::InvalidPart(val)),
+                };
+            },
+            "--input" => input = Some(args.next().ok_or(CliError::MissingValue("--input"))?),
+            "--time" => time = true,
+            "--format" => {
+                let val = args.next().ok_or(CliError::MissingValue("--format"))?;
+                format = match val.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    _ => return Err(CliError::InvalidFormat(val)),
+                };
+            },
+            _ => return Err(CliError::UnexpectedArgument(arg)),
+        }
+    }
+
+    if day.is_none() && input.is_some() { return Err(CliError::InputWithAll); }
+    Ok(RunArgs { day, part, input, time, format })
+}
+
+fn parse_check_args<I: Iterator<Item = String>>(args: I) -> Result<CheckArgs, CliError> {
+    let mut args = args.peekable();
+    let day = match args.next_if(|arg| !arg.starts_with("--")) {
+        Some(day) => Some(parse_day(day)?),
+        None => None,
+    };
+
+    let mut answers = None;
+    let mut update = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--answers" => answers = Some(args.next().ok_or(CliError::MissingValue("--answers"))?),
+            "--update" => update = true,
+            _ => return Err(CliError::UnexpectedArgument(arg)),
+        }
+    }
+
+    let answers = answers.unwrap_or_else(|| DEFAULT_ANSWERS.to_owned());
+    Ok(CheckArgs { day, answers, update })
+}
+
+fn default_input(day: u8) -> String {
+    format!("input_data/day{}_input.txt", day)
+}
+
+// "-" reads the puzzle input from stdin
+fn read_input(path: &str) -> Result<String, CliError> {
+    if path == "-" {
+        let mut buf = String::new();
+        return io::stdin().read_to_string(&mut buf)
+            .map(|_| buf)
+            .map_err(|e| CliError::ReadInput("<stdin>".to_owned(), e));
+    }
+    fs::read_to_string(path).map_err(|e| CliError::ReadInput(path.to_owned(), e))
+}
+
+// like `read_input`, but the input is read as it is parsed instead of all at once
+fn open_input(path: &str) -> Result<Box<dyn BufRead>, CliError> {
+    if path == "-" { return Ok(Box::new(io::stdin().lock())); }
+    let file = fs::File::open(path).map_err(|e| CliError::ReadInput(path.to_owned(), e))?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+fn run<I: Iterator<Item = String>>(mut args: I) -> Result<(), CliError> {
+    match args.next().as_deref() {
+        Some("run") => run_days(parse_run_args(args)?),
+        Some("check") => check(parse_check_args(args)?),
+        Some(cmd) => Err(CliError::UnknownCommand(cmd.to_owned())),
+        None => Err(CliError::MissingCommand),
+    }
+}
+
+type DayResult = (u8, Result<(Answers, Timings), CliError>);
+
+fn run_days(run_args: RunArgs) -> Result<(), CliError> {
+    let days = match run_args.day {
+        Some(day) => day..=day,
+        None => 1..=LAST_DAY,
+    };
+    let mut results: Vec<DayResult> = Vec::new();
+    for day in days {
+        let input = run_args.input.clone().unwrap_or_else(|| default_input(day));
+        // the phases can only be timed separately once the whole input has been read, and JSON always has timings
+        let result = if run_args.time || run_args.format == Format::Json {
+            read_input(&input).and_then(|file_data| {
+                solve_day_timed(day, &file_data, run_args.part)
+                    .expect("day is validated when parsing the arguments")
+                    .map_err(|e| CliError::Solve(day, e))
+            })
+        }
+        else {
+            open_input(&input).and_then(|reader| {
+                solve_day_reader(day, reader, run_args.part)
+                    .expect("day is validated when parsing the arguments")
+                    .map(|answers| (answers, Timings::default()))
+                    .map_err(|e| CliError::Solve(day, e))
+            })
+        };
+
+        // when running all days, a failing day is reported without stopping the rest
+        if run_args.format == Format::Text {
+            match &result {
+                Ok((answers, _)) => {
+                    if let Some(ans) = &answers.part1 { print_answer(day, 1, ans); }
+                    if let Some(ans) = &answers.part2 { print_answer(day, 2, ans); }
+                },
+                Err(e) if run_args.day.is_none() => print_causes(e, |s| eprintln!("{}", s)),
+                Err(_) => (),
+            }
+        }
+        results.push((day, result));
+    }
+
+    match run_args.format {
+        Format::Text if run_args.time => print_timing_table(&results),
+        Format::Text => (),
+        Format::Json => println!("{}", results_to_json(&results)),
+    }
+
+    if run_args.day.is_some() && run_args.format == Format::Text {
+        if let Some((_, Err(e))) = results.pop() { return Err(e); }
+    }
+    match results.iter().filter(|(_, result)| result.is_err()).count() {
+        0 => Ok(()),
+        count => Err(CliError::RunFailed(count)),
+    }
+}
+
+fn print_timing_table(results: &[DayResult]) {
+    let print_row = |cols: [&str; 5]| {
+        println!("{:>5}  {:>10}  {:>10}  {:>10}  {:>10}", cols[0], cols[1], cols[2], cols[3], cols[4]);
+    };
+    let optional_duration = |d: Option<Duration>| d.map_or_else(|| "-".to_owned(), format_duration);
+
+    println!();
+    print_row(["Day", "Parse", "Part 1", "Part 2", "Total"]);
+    let mut total = Duration::ZERO;
+    for (day, result) in results {
+        let day = day.to_string();
+        match result {
+            Ok((_, timings)) => {
+                total += timings.total();
+                print_row([
+                    &day, &format_duration(timings.parse), &optional_duration(timings.part1),
+                    &optional_duration(timings.part2), &format_duration(timings.total())
+                ]);
+            },
+            Err(_) => print_row([&day, "failed", "", "", ""]),
+        }
+    }
+    print_row(["All", "", "", "", &format_duration(total)]);
+}
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs_f64();
+    if secs < 1e-3 { format!("{:.1}µs", secs * 1e6) }
+    else if secs < 1.0 { format!("{:.2}ms", secs * 1e3) }
+    else { format!("{:.3}s", secs) }
+}
+
+// one object per day, with the answer, time in nanoseconds and extras of each requested part
+fn results_to_json(results: &[DayResult]) -> String {
+    let mut days = Vec::new();
+    let mut total = Duration::ZERO;
+    for (day, result) in results {
+        let mut fields = vec![format!("\"day\": {}", day)];
+        match result {
+            Ok((answers, timings)) => {
+                total += timings.total();
+                fields.push(format!("\"parse_time_ns\": {}", timings.parse.as_nanos()));
+                let parts = [
+                    ("part1", &answers.part1, timings.part1, &answers.part1_extras),
+                    ("part2", &answers.part2, timings.part2, &answers.part2_extras),
+                ];
+                for (key, ans, time, extras) in parts {
+                    let ans = match ans {
+                        Some(ans) => ans,
+                        None => continue,
+                    };
+                    let mut part_fields = vec![format!("\"answer\": {}", json_string(ans))];
+                    if let Some(d) = time { part_fields.push(format!("\"time_ns\": {}", d.as_nanos())); }
+                    if !extras.is_empty() {
+                        let extras = extras.iter()
+                            .map(|(name, extra)| format!("{}: {}", json_string(name), extra_to_json(extra)))
+                            .collect::<Vec<_>>();
+                        part_fields.push(format!("\"extras\": {{{}}}", extras.join(", ")));
+                    }
+                    fields.push(format!("\"{}\": {{{}}}", key, part_fields.join(", ")));
+                }
+                fields.push(format!("\"total_time_ns\": {}", timings.total().as_nanos()));
+            },
+            Err(e) => fields.push(format!("\"error\": {}", json_string(&error_chain(e)))),
+        }
+        days.push(format!("    {{{}}}", fields.join(", ")));
+    }
+
+    format!("{{\n  \"days\": [\n{}\n  ],\n  \"total_time_ns\": {}\n}}", days.join(",\n"), total.as_nanos())
+}
+
+fn extra_to_json(extra: &Extra) -> String {
+    match extra {
+        Extra::Int(x) => x.to_string(),
+        Extra::Text(s) => json_string(s),
+        Extra::List(items) => format!("[{}]", items.iter().map(extra_to_json).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+// an error and its causes on a single line
+fn error_chain(e: &dyn Error) -> String {
+    let mut chain = e.to_string();
+    let mut source = e.source();
+    while let Some(src) = source {
+        chain.push_str(": ");
+        chain.push_str(&src.to_string());
+        source = src.source();
+    }
+    chain
+}
+
+// runs every requested day against its stored input, and reports whether each answer matches the stored one
+fn check(check_args: CheckArgs) -> Result<(), CliError> {
+    let path = check_args.answers;
+    let mut store = match fs::read_to_string(&path) {
+        Ok(s) => s.parse::<AnswerStore>().map_err(|e| CliError::ParseAnswers(path.clone(), e))?,
+        Err(e) if check_args.update && e.kind() == io::ErrorKind::NotFound => AnswerStore::new(),
+        Err(e) => return Err(CliError::ReadAnswers(path, e)),
+    };
+
+    let days = match check_args.day {
+        Some(day) => day..=day,
+        None => 1..=LAST_DAY,
+    };
+    let (mut passed, mut failed, mut changed, mut unknown) = (0, 0, 0, 0);
+    for day in days {
+        let answers = read_input(&default_input(day)).and_then(|file_data| {
+            solve_day(day, &file_data, None)
+                .expect("days within 1..=LAST_DAY have a solution")
+                .map_err(|e| CliError::Solve(day, e))
+        });
+        let answers = match answers {
+            Ok(x) => x,
+            Err(e) => {
+                println!("Day {}: fail", day);
+                print_causes(&e, |s| println!("  {}", s));
+                failed += 2;
+                continue;
+            },
+        };
+
+        for (part, ans) in [(Part::One, answers.part1), (Part::Two, answers.part2)] {
+            let ans = ans.expect("both parts are solved");
+            let part_number = if part == Part::One { 1 } else { 2 };
+            let verdict = store.verdict(day, part, &ans);
+            match verdict {
+                Verdict::Pass => passed += 1,
+                Verdict::Changed => changed += 1,
+                Verdict::Unknown => unknown += 1,
+            }
+            println!("Day {}, Part {}: {}", day, part_number, verdict);
+            if verdict == Verdict::Changed {
+                print_labelled("expected", store.get(day, part).unwrap_or_default());
+                print_labelled("got", &ans);
+            }
+            if check_args.update && verdict != Verdict::Pass {
+                store.set(day, part, ans);
+            }
+        }
+    }
+
+    println!("{} passed, {} failed, {} changed, {} unknown", passed, failed, changed, unknown);
+    if check_args.update {
+        if changed + unknown > 0 {
+            fs::write(&path, store.to_string()).map_err(|e| CliError::WriteAnswers(path.clone(), e))?;
+            println!("Recorded {} answer(s) in \"{}\".", changed + unknown, path);
+        }
+        changed = 0;
+    }
+    match failed + changed {
+        0 => Ok(()),
+        count => Err(CliError::CheckFailed(count)),
+    }
+}
+
+// multi-line answers (e.g. day 13's folded paper) start on their own line
+fn print_answer(day: u8, part: u8, ans: &str) {
+    let separator = if ans.contains('\n') { "\n" } else { " " };
+    println!("Day {}, Part {}:{}{}", day, part, separator, ans);
+}
+
+fn print_labelled(label: &str, ans: &str) {
+    let separator = if ans.contains('\n') { "\n" } else { " " };
+    println!("  {}:{}{}", label, separator, ans);
+}
+
+fn print_causes(e: &dyn Error, print: impl Fn(String)) {
+    print(format!("error: {}", e));
+    let mut source = e.source();
+    while let Some(src) = source {
+        print(format!("  caused by: {}", src));
+        source = src.source();
+    }
+}
 
 fn main() {
-    // read input data from file
-    let filepath = "input_data/day21_input.txt";
-    let file_data = fs::read_to_string(filepath).unwrap_or_else(|e| {
-        eprintln!("Error reading file \"{}\"! : {}", filepath, e);
+    if let Err(e) = run(env::args().skip(1)) {
+        print_causes(&e, |s| eprintln!("{}", s));
+        if e.is_usage_error() {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
         process::exit(1);
-    });
-
-    day21::day21_main(&file_data);
+    }
 }
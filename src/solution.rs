@@ -0,0 +1,162 @@
+use std::io::{self, BufRead};
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+use crate::PuzzleError;
+
+/// The answers of the requested parts of `S`, where parts that were not requested are `None`.
+pub type PartAnswers<S> = (Option<<S as Solution>::Answer1>, Option<<S as Solution>::Answer2>);
+
+/// A puzzle solution, split into parsing the input and solving each part on the parsed input.
+pub trait Solution {
+    type Input;
+    type Answer1: Display;
+    type Answer2: Display;
+
+    fn parse(file_data: &str) -> Result<Self::Input, PuzzleError>;
+    fn part1(input: &Self::Input) -> Result<Self::Answer1, PuzzleError>;
+    fn part2(input: &Self::Input) -> Result<Self::Answer2, PuzzleError>;
+
+    /// Intermediate results behind the part 1 answer which are worth reporting, such as the initial velocity that
+    /// reaches day 17's highest point. There are none by default.
+    fn part1_extras(_answer: &Self::Answer1) -> Extras {
+        Vec::new()
+    }
+
+    /// Like `part1_extras`, but for the part 2 answer.
+    fn part2_extras(_answer: &Self::Answer2) -> Extras {
+        Vec::new()
+    }
+
+    /// Parses the input as it is read from `reader`. By default the whole input is read before it is parsed, but
+    /// solutions with line-based inputs parse each line as soon as it is read.
+    fn parse_reader<R: BufRead>(mut reader: R) -> Result<Self::Input, PuzzleError> {
+        let mut file_data = String::new();
+        reader.read_to_string(&mut file_data)?;
+        Self::parse(&file_data)
+    }
+
+    /// Solves the requested `part`, or both parts if `part` is `None`, as the input is read from `reader`. By
+    /// default the input is parsed with `parse_reader` first, but solutions can avoid storing the whole input by
+    /// solving in a single pass instead.
+    fn solve_stream<R: BufRead>(reader: R, part: Option<Part>) -> Result<PartAnswers<Self>, PuzzleError> {
+        let input = Self::parse_reader(reader)?;
+        let part1 = if part == Some(Part::Two) { None } else { Some(Self::part1(&input)?) };
+        let part2 = if part == Some(Part::One) { None } else { Some(Self::part2(&input)?) };
+        Ok((part1, part2))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Part {
+    One, Two
+}
+
+/// A structured value reported alongside an answer.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Extra {
+    Int(i64), Text(String), List(Vec<Extra>)
+}
+
+/// Named values reported alongside an answer, in the order they should be shown.
+pub type Extras = Vec<(&'static str, Extra)>;
+
+/// The displayed answers of a solution, where parts that were not requested are `None` and have no extras.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Answers {
+    pub part1: Option<String>,
+    pub part2: Option<String>,
+    pub part1_extras: Extras,
+    pub part2_extras: Extras,
+}
+
+/// How long each phase of a solution took, where parts that were not requested are `None`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Timings {
+    pub parse: Duration,
+    pub part1: Option<Duration>,
+    pub part2: Option<Duration>,
+}
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.parse + self.part1.unwrap_or_default() + self.part2.unwrap_or_default()
+    }
+}
+
+/// Solves the requested `part` of `S`, or both parts if `part` is `None`.
+pub fn solve<S: Solution>(file_data: &str, part: Option<Part>) -> Result<Answers, PuzzleError> {
+    solve_timed::<S>(file_data, part).map(|(answers, _)| answers)
+}
+
+/// Like `solve`, but the input is read from `reader` with `Solution::solve_stream`.
+pub fn solve_reader<S: Solution, R: BufRead>(reader: R, part: Option<Part>) -> Result<Answers, PuzzleError> {
+    let (part1, part2) = S::solve_stream(reader, part)?;
+    Ok(Answers {
+        part1: part1.as_ref().map(|ans| ans.to_string()),
+        part2: part2.as_ref().map(|ans| ans.to_string()),
+        part1_extras: part1.as_ref().map(S::part1_extras).unwrap_or_default(),
+        part2_extras: part2.as_ref().map(S::part2_extras).unwrap_or_default(),
+    })
+}
+
+/// Like `solve`, but also measures how long parsing and each part took.
+pub fn solve_timed<S: Solution>(file_data: &str, part: Option<Part>) -> Result<(Answers, Timings), PuzzleError> {
+    let start = Instant::now();
+    let input = S::parse(file_data)?;
+    let mut timings = Timings { parse: start.elapsed(), ..Timings::default() };
+
+    let mut answers = Answers::default();
+    if part != Some(Part::Two) {
+        let start = Instant::now();
+        let ans = S::part1(&input)?;
+        timings.part1 = Some(start.elapsed());
+        answers.part1 = Some(ans.to_string());
+        answers.part1_extras = S::part1_extras(&ans);
+    }
+    if part != Some(Part::One) {
+        let start = Instant::now();
+        let ans = S::part2(&input)?;
+        timings.part2 = Some(start.elapsed());
+        answers.part2 = Some(ans.to_string());
+        answers.part2_extras = S::part2_extras(&ans);
+    }
+    Ok((answers, timings))
+}
+
+/// The lines of `reader` along with their line numbers, where blank lines at the end of the input are skipped.
+pub(crate) fn read_lines<R: BufRead>(reader: R) -> InputLines<R> {
+    InputLines { lines: reader.lines(), line_number: 0, blank_lines: 0, held_line: None }
+}
+
+pub(crate) struct InputLines<R> {
+    lines: io::Lines<R>,
+    line_number: usize,
+    blank_lines: usize,     // blank lines which have been read but not yielded yet
+    held_line: Option<String>,  // the line following `blank_lines`
+}
+impl<R: BufRead> Iterator for InputLines<R> {
+    type Item = Result<(usize, String), PuzzleError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // blank lines are only yielded once a line that is not blank follows them
+        if self.blank_lines > 0 && self.held_line.is_some() {
+            self.blank_lines -= 1;
+            self.line_number += 1;
+            return Some(Ok((self.line_number, String::new())));
+        }
+        if let Some(line) = self.held_line.take() {
+            self.line_number += 1;
+            return Some(Ok((self.line_number, line)));
+        }
+
+        loop {
+            match self.lines.next()? {
+                Err(e) => return Some(Err(e.into())),
+                Ok(line) if line.trim().is_empty() => self.blank_lines += 1,
+                Ok(line) => {
+                    self.held_line = Some(line);
+                    return self.next();
+                },
+            }
+        }
+    }
+}